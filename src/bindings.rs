@@ -11,29 +11,41 @@ use mlua::chunk;
 use mlua::prelude::*;
 
 use self::{
+    arpeggiator::ArpeggiatorUserData,
+    markov::MarkovUserData,
     note::NoteUserData,
     rhythm::rhythm_from_userdata,
     sequence::SequenceUserData,
     unwrap::{bad_argument_error, validate_table_properties},
+    weighted::WeightedChoiceUserData,
 };
 
+#[cfg(feature = "tidal")]
+use self::cycle::CycleUserData;
+
 use crate::{
     event::InstrumentId,
     rhythm::{beat_time::BeatTimeRhythm, second_time::SecondTimeRhythm, Rhythm},
     time::BeatTimeBase,
-    Scale,
+    tuning::KeyboardMapping,
+    Scale, Tuning,
 };
 
 // ---------------------------------------------------------------------------------------------
 
 // private binding impls
+mod arpeggiator;
 mod callback;
+#[cfg(feature = "tidal")]
+mod cycle;
+mod markov;
 mod note;
 mod rhythm;
 mod scale;
 mod sequence;
 mod timeout;
 mod unwrap;
+mod weighted;
 
 // public re-exports
 pub use callback::{clear_lua_callback_errors, has_lua_callback_errors, lua_callback_errors};
@@ -165,13 +177,34 @@ fn register_global_bindings(
 ) -> LuaResult<()> {
     let globals = lua.globals();
 
-    // function scale(note, mode|intervals)
+    // function scale(note, mode|intervals|scala_file, [mapping_file])
     globals.raw_set(
         "scale",
         lua.create_function(
-            |lua, (note, mode_or_intervals): (LuaValue, LuaValue)| -> LuaResult<Scale> {
+            |lua,
+             (note, mode_or_intervals, mapping_file): (LuaValue, LuaValue, Option<String>)|
+             -> LuaResult<Scale> {
                 let note = FromLua::from_lua(note, lua)?;
                 if let Some(mode) = mode_or_intervals.as_str() {
+                    if mode.to_ascii_lowercase().ends_with(".scl") {
+                        let mut tuning = Tuning::from_scl_file(mode)
+                            .map_err(|err| bad_argument_error("scale", "mode", 1, &err))?;
+                        if let Some(mapping_file) = mapping_file {
+                            let mapping = KeyboardMapping::from_kbm_file(mapping_file)
+                                .map_err(|err| bad_argument_error("scale", "mapping", 3, &err))?;
+                            tuning = tuning.with_mapping(mapping);
+                        }
+                        return Scale::try_from((note, &tuning))
+                            .map_err(|err| bad_argument_error("scale", "mode", 1, &err));
+                    }
+                    if mapping_file.is_some() {
+                        return Err(bad_argument_error(
+                            "scale",
+                            "mapping",
+                            3,
+                            "keyboard mappings can only be applied to Scala scale (.scl) files",
+                        ));
+                    }
                     match Scale::try_from((note, mode)) {
                         Ok(scale) => Ok(scale),
                         Err(err) => Err(bad_argument_error(
@@ -240,6 +273,76 @@ fn register_global_bindings(
         })?,
     )?;
 
+    // function cycle(input)
+    #[cfg(feature = "tidal")]
+    globals.raw_set(
+        "cycle",
+        lua.create_function(|lua, arg: LuaValue| -> LuaResult<CycleUserData> {
+            if let Some(input) = arg.as_string() {
+                let rand_seed = {
+                    lua.app_data_ref::<LuaAppData>()
+                        .expect("Failed to access Lua app data")
+                        .rand_seed
+                };
+                CycleUserData::from(input.clone(), rand_seed)
+            } else {
+                Err(bad_argument_error(
+                    "cycle",
+                    "input",
+                    1,
+                    "expecting a mini-notation string",
+                ))
+            }
+        })?,
+    )?;
+
+    // function arp(emit, options)
+    globals.raw_set(
+        "arp",
+        lua.create_function({
+            let timeout_hook = timeout_hook.clone();
+            let time_base = *time_base;
+            move |lua, args: (LuaValue, Option<LuaTable>)| -> LuaResult<ArpeggiatorUserData> {
+                let rand_seed = {
+                    lua.app_data_ref::<LuaAppData>()
+                        .expect("Failed to access Lua app data")
+                        .rand_seed
+                };
+                ArpeggiatorUserData::from(lua, &timeout_hook, &time_base, args, rand_seed)
+            }
+        })?,
+    )?;
+
+    // function markov(sequence, options)
+    globals.raw_set(
+        "markov",
+        lua.create_function(
+            |lua, args: (LuaValue, Option<LuaTable>)| -> LuaResult<MarkovUserData> {
+                let rand_seed = {
+                    lua.app_data_ref::<LuaAppData>()
+                        .expect("Failed to access Lua app data")
+                        .rand_seed
+                };
+                MarkovUserData::from(args, rand_seed)
+            },
+        )?,
+    )?;
+
+    // function choose(choices)
+    globals.raw_set(
+        "choose",
+        lua.create_function(
+            |lua, choices: LuaTable| -> LuaResult<WeightedChoiceUserData> {
+                let rand_seed = {
+                    lua.app_data_ref::<LuaAppData>()
+                        .expect("Failed to access Lua app data")
+                        .rand_seed
+                };
+                WeightedChoiceUserData::from(choices, rand_seed)
+            },
+        )?,
+    )?;
+
     // function rhythm { args... }
     globals.raw_set(
         "rhythm",
@@ -0,0 +1,187 @@
+use mlua::prelude::*;
+
+use super::{
+    unwrap::{bad_argument_error, event_iter_from_value, validate_table_properties},
+    LuaTimeoutHook,
+};
+
+use crate::{
+    event::arpeggiator::{ArpeggiatorEventIter, ArpeggiatorMode},
+    BeatTimeBase,
+};
+
+// ---------------------------------------------------------------------------------------------
+
+// Arpeggiator
+#[derive(Debug)]
+pub struct ArpeggiatorUserData {
+    pub arpeggiator: ArpeggiatorEventIter,
+}
+
+impl ArpeggiatorUserData {
+    pub fn from(
+        lua: &Lua,
+        timeout_hook: &LuaTimeoutHook,
+        time_base: &BeatTimeBase,
+        (value, options): (LuaValue, Option<LuaTable>),
+        seed: Option<[u8; 32]>,
+    ) -> LuaResult<Self> {
+        let event_iter = event_iter_from_value(lua, timeout_hook, &value, time_base)?;
+        let mut arpeggiator = ArpeggiatorEventIter::new(event_iter, seed);
+        if let Some(options) = options {
+            validate_table_properties(&options, &["mode", "octaves", "rate"])?;
+            if let Some(mode) = options.get::<_, Option<String>>("mode")? {
+                let mode = ArpeggiatorMode::try_from(mode.as_str()).map_err(|err| {
+                    bad_argument_error(
+                        "arp",
+                        "mode",
+                        2,
+                        &format!(
+                            "{}, valid modes are: {}",
+                            err,
+                            ArpeggiatorMode::mode_names().join(", ")
+                        ),
+                    )
+                })?;
+                arpeggiator = arpeggiator.with_mode(mode);
+            }
+            if let Some(octaves) = options.get::<_, Option<usize>>("octaves")? {
+                if octaves == 0 {
+                    return Err(bad_argument_error(
+                        "arp",
+                        "octaves",
+                        2,
+                        "octaves must be > 0",
+                    ));
+                }
+                arpeggiator = arpeggiator.with_octaves(octaves);
+            }
+            if let Some(rate) = options.get::<_, Option<f32>>("rate")? {
+                if !(rate.is_finite() && rate > 0.0) {
+                    return Err(bad_argument_error(
+                        "arp",
+                        "rate",
+                        2,
+                        "rate must be a finite number > 0",
+                    ));
+                }
+                arpeggiator = arpeggiator.with_rate(rate);
+            }
+        }
+        Ok(ArpeggiatorUserData { arpeggiator })
+    }
+}
+
+impl LuaUserData for ArpeggiatorUserData {}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        bindings::*,
+        event::{Event, NoteEvent},
+        note::Note,
+        rhythm::beat_time::BeatTimeRhythm,
+    };
+
+    #[test]
+    fn arpeggiator() -> LuaResult<()> {
+        // create a new engine and register bindings
+        let (mut lua, mut timeout_hook) = new_engine()?;
+        register_bindings(
+            &mut lua,
+            &timeout_hook,
+            &BeatTimeBase {
+                beats_per_min: 120.0,
+                beats_per_bar: 4,
+                samples_per_sec: 44100,
+            },
+        )?;
+
+        // reset timeout
+        timeout_hook.reset();
+
+        // invalid arguments
+        assert!(lua
+            .load(r#"arp(chord("c4", "major"), {mode = "sideways"})"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"arp(chord("c4", "major"), {octaves = 0})"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"arp(chord("c4", "major"), {rate = 0})"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"arp(chord("c4", "major"), {rate = 0/0})"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"arp(chord("c4", "major"), {rate = math.huge})"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"arp(chord("c4", "major"), {speed = 1})"#)
+            .exec()
+            .is_err());
+        // too large values get clamped
+        assert!(lua
+            .load(r#"arp(chord("c4", "major"), {octaves = 1e12, rate = 1e12})"#)
+            .exec()
+            .is_ok());
+
+        // arpeggiator as emitter
+        let rhythm = lua
+            .load(
+                r#"
+                return rhythm {
+                    unit = "beats",
+                    emit = arp(chord("c4", "minor7"), {mode = "updown", octaves = 2})
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let rhythm = rhythm
+            .as_userdata()
+            .ok_or(LuaError::RuntimeError("No user data".to_string()))?
+            .borrow::<BeatTimeRhythm>()?
+            .clone();
+        let notes = rhythm
+            .take(16)
+            .map(|item| match item.event {
+                Some(Event::NoteEvents(note_events)) => note_events
+                    .into_iter()
+                    .flatten()
+                    .map(|note_event: NoteEvent| note_event.note)
+                    .collect::<Vec<_>>(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        assert_eq!(
+            notes,
+            vec![
+                Note::C4,
+                Note::Ds4,
+                Note::G4,
+                Note::As4,
+                Note::C5,
+                Note::Ds5,
+                Note::G5,
+                Note::As5,
+                Note::G5,
+                Note::Ds5,
+                Note::C5,
+                Note::As4,
+                Note::G4,
+                Note::Ds4,
+                Note::C4,
+                Note::Ds4,
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+use mlua::prelude::*;
+
+use super::unwrap::bad_argument_error;
+
+use crate::event::cycle::CycleEventIter;
+
+// ---------------------------------------------------------------------------------------------
+
+// Cycle
+#[derive(Clone, Debug)]
+pub struct CycleUserData {
+    pub cycle: CycleEventIter,
+}
+
+impl CycleUserData {
+    pub fn from(arg: LuaString, seed: Option<[u8; 32]>) -> LuaResult<Self> {
+        // parse errors contain the location of the error in the mini-notation string
+        let mini = arg.to_str()?;
+        let cycle = CycleEventIter::from_mini_with_seed(mini, seed)
+            .map_err(|err| bad_argument_error("cycle", "input", 1, &err.to_string()))?;
+        Ok(CycleUserData { cycle })
+    }
+
+    // Apply the given Lua transform function on the given cycle. The function must return a cycle.
+    fn transform<'lua>(
+        func: &'static str,
+        fun: &LuaFunction<'lua>,
+        cycle: CycleEventIter,
+    ) -> LuaResult<CycleEventIter> {
+        let result = fun.call::<_, LuaValue>(CycleUserData { cycle })?;
+        match result.as_userdata() {
+            Some(userdata) => Ok(userdata.borrow::<CycleUserData>()?.cycle.clone()),
+            None => Err(bad_argument_error(
+                func,
+                "transform",
+                2,
+                "transform function must return a cycle",
+            )),
+        }
+    }
+}
+
+impl LuaUserData for CycleUserData {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("fast", |_lua, this, factor: f64| {
+            if !(factor.is_finite() && factor > 0.0) {
+                return Err(bad_argument_error(
+                    "fast",
+                    "factor",
+                    1,
+                    "factor must be a finite number > 0",
+                ));
+            }
+            let cycle = this.cycle.clone().fast(factor);
+            Ok(CycleUserData { cycle })
+        });
+
+        methods.add_method("slow", |_lua, this, factor: f64| {
+            if !(factor.is_finite() && factor > 0.0) {
+                return Err(bad_argument_error(
+                    "slow",
+                    "factor",
+                    1,
+                    "factor must be a finite number > 0",
+                ));
+            }
+            let cycle = this.cycle.clone().slow(factor);
+            Ok(CycleUserData { cycle })
+        });
+
+        methods.add_method("rev", |_lua, this, ()| {
+            let cycle = this.cycle.clone().rev();
+            Ok(CycleUserData { cycle })
+        });
+
+        methods.add_method("every", |_lua, this, (n, fun): (usize, LuaFunction)| {
+            if n == 0 {
+                return Err(bad_argument_error("every", "n", 1, "n must be > 0"));
+            }
+            let transformed = Self::transform("every", &fun, this.cycle.clone())?;
+            let cycle = this.cycle.clone().every(n, |_| transformed);
+            Ok(CycleUserData { cycle })
+        });
+
+        methods.add_method("off", |_lua, this, (time, fun): (f64, LuaFunction)| {
+            if !time.is_finite() {
+                return Err(bad_argument_error(
+                    "off",
+                    "time",
+                    1,
+                    "time must be a finite number",
+                ));
+            }
+            let mut result = Ok(());
+            let cycle = this.cycle.clone().off(time, |shifted| {
+                match Self::transform("off", &fun, shifted.clone()) {
+                    Ok(transformed) => transformed,
+                    Err(err) => {
+                        result = Err(err);
+                        shifted
+                    }
+                }
+            });
+            result.map(|_| CycleUserData { cycle })
+        });
+
+        methods.add_method("degrade_by", |_lua, this, amount: f64| {
+            if !(0.0..=1.0).contains(&amount) {
+                return Err(bad_argument_error(
+                    "degrade_by",
+                    "amount",
+                    1,
+                    "amount must be in range [0.0..=1.0]",
+                ));
+            }
+            let cycle = this.cycle.clone().degrade_by(amount);
+            Ok(CycleUserData { cycle })
+        });
+
+        methods.add_method("palindrome", |_lua, this, ()| {
+            let cycle = this.cycle.clone().palindrome();
+            Ok(CycleUserData { cycle })
+        });
+
+        methods.add_method("iter", |_lua, this, n: usize| {
+            if n == 0 {
+                return Err(bad_argument_error("iter", "n", 1, "n must be > 0"));
+            }
+            let cycle = this.cycle.clone().iter(n);
+            Ok(CycleUserData { cycle })
+        });
+
+        methods.add_method("chunk", |_lua, this, (n, fun): (usize, LuaFunction)| {
+            if n == 0 {
+                return Err(bad_argument_error("chunk", "n", 1, "n must be > 0"));
+            }
+            let transformed = Self::transform("chunk", &fun, this.cycle.clone())?;
+            let cycle = this.cycle.clone().chunk(n, |_| transformed);
+            Ok(CycleUserData { cycle })
+        });
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::CycleUserData;
+    use crate::{
+        bindings::*,
+        event::{Event, EventIter, NoteEvent},
+        note::Note,
+        rhythm::{beat_time::BeatTimeRhythm, RhythmIterItem},
+        PulseIterItem,
+    };
+
+    #[test]
+    fn cycle() -> LuaResult<()> {
+        // create a new engine and register bindings
+        let (mut lua, mut timeout_hook) = new_engine()?;
+        register_bindings(
+            &mut lua,
+            &timeout_hook,
+            &BeatTimeBase {
+                beats_per_min: 120.0,
+                beats_per_bar: 4,
+                samples_per_sec: 44100,
+            },
+        )?;
+
+        // reset timeout
+        timeout_hook.reset();
+
+        // parse errors
+        assert!(lua.load(r#"cycle("c4 [e4")"#).exec().is_err());
+        let error = lua.load(r#"cycle("c4 e4'qwe")"#).exec().unwrap_err();
+        assert!(error.to_string().contains("unknown chord mode 'qwe'"));
+        assert!(error.to_string().contains("at line 1, column 7"));
+        assert!(lua.load(r#"cycle({"c4"})"#).exec().is_err());
+
+        // cycle as emitter
+        let rhythm = lua
+            .load(
+                r#"
+                return rhythm {
+                    unit = "bars",
+                    emit = cycle("c4 e4")
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let mut rhythm = rhythm
+            .as_userdata()
+            .ok_or(LuaError::RuntimeError("No user data".to_string()))?
+            .borrow_mut::<BeatTimeRhythm>()?;
+        assert_eq!(
+            rhythm.next(),
+            Some(RhythmIterItem {
+                time: 0,
+                event: Some(Event::NoteEvents(vec![
+                    Some(NoteEvent {
+                        instrument: None,
+                        note: Note::C4,
+                        volume: 1.0,
+                        panning: 0.0,
+                        delay: 0.0,
+                        length: Some(0.5),
+                        glide: None,
+                        parameters: BTreeMap::new()
+                    }),
+                    Some(NoteEvent {
+                        instrument: None,
+                        note: Note::E4,
+                        volume: 1.0,
+                        panning: 0.0,
+                        delay: 0.5,
+                        length: Some(0.5),
+                        glide: None,
+                        parameters: BTreeMap::new()
+                    })
+                ])),
+                duration: 88200
+            })
+        );
+        // note-offs are scheduled at the end of each note
+        let note_off = Some(NoteEvent::from(Note::OFF));
+        assert_eq!(
+            rhythm.next(),
+            Some(RhythmIterItem {
+                time: 44100,
+                event: Some(Event::NoteEvents(vec![note_off.clone()])),
+                duration: 0
+            })
+        );
+        assert_eq!(
+            rhythm.next(),
+            Some(RhythmIterItem {
+                time: 88200,
+                event: Some(Event::NoteEvents(vec![None, note_off])),
+                duration: 0
+            })
+        );
+        assert_eq!(rhythm.next().map(|item| item.time), Some(88200));
+        Ok(())
+    }
+
+    #[test]
+    fn cycle_transforms() -> LuaResult<()> {
+        // create a new engine and register bindings
+        let (mut lua, mut timeout_hook) = new_engine()?;
+        register_bindings(
+            &mut lua,
+            &timeout_hook,
+            &BeatTimeBase {
+                beats_per_min: 120.0,
+                beats_per_bar: 4,
+                samples_per_sec: 44100,
+            },
+        )?;
+
+        // reset timeout
+        timeout_hook.reset();
+
+        // invalid arguments
+        assert!(lua.load(r#"cycle("c4 e4"):fast(0)"#).exec().is_err());
+        assert!(lua.load(r#"cycle("c4 e4"):fast(0/0)"#).exec().is_err());
+        assert!(lua
+            .load(r#"cycle("c4 e4"):slow(math.huge)"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"cycle("c4 e4"):off(0/0, function(c) return c end)"#)
+            .exec()
+            .is_err());
+        assert!(lua.load(r#"cycle("c4 e4"):degrade_by(2)"#).exec().is_err());
+        assert!(lua
+            .load(r#"cycle("c4 e4"):every(0, function(c) return c end)"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"cycle("c4 e4"):every(2, function(c) return 1 end)"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"cycle("c4 e4"):off(0.25, function(c) end)"#)
+            .exec()
+            .is_err());
+
+        // transformed cycles
+        let cycle = lua
+            .load(
+                r#"
+                return cycle("c4 e4")
+                  :every(2, function(c) return c:fast(2) end)
+                  :off(0.25, function(c) return c:rev() end)
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let mut cycle = cycle
+            .as_userdata()
+            .ok_or(LuaError::RuntimeError("No user data".to_string()))?
+            .borrow::<CycleUserData>()?
+            .cycle
+            .clone();
+        let pulse = PulseIterItem::default();
+        let notes = |event: Option<Event>| match event {
+            Some(Event::NoteEvents(notes)) => notes
+                .into_iter()
+                .map(|note| note.map(|note| note.note))
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+        assert_eq!(
+            notes(cycle.run(pulse, 1, true)),
+            vec![
+                Note::C4,
+                Note::E4,
+                Note::C4,
+                Note::E4,
+                Note::C4,
+                Note::E4,
+                Note::C4
+            ]
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            notes(cycle.run(pulse, 1, true)),
+            vec![Note::C4, Note::E4, Note::C4, Note::E4]
+                .into_iter()
+                .map(Some)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
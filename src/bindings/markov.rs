@@ -0,0 +1,138 @@
+use mlua::prelude::*;
+
+use super::{
+    sequence::SequenceUserData,
+    unwrap::{bad_argument_error, validate_table_properties},
+};
+
+use crate::event::{markov::MarkovEventIter, Event};
+
+// ---------------------------------------------------------------------------------------------
+
+// Markov
+#[derive(Clone, Debug)]
+pub struct MarkovUserData {
+    pub markov: MarkovEventIter,
+}
+
+impl MarkovUserData {
+    pub fn from(
+        (value, options): (LuaValue, Option<LuaTable>),
+        seed: Option<[u8; 32]>,
+    ) -> LuaResult<Self> {
+        // convert sequences or anything a sequence can be created from
+        let notes = match value.as_userdata() {
+            Some(userdata) if userdata.is::<SequenceUserData>() => {
+                userdata.borrow::<SequenceUserData>()?.notes.clone()
+            }
+            _ => SequenceUserData::from(LuaMultiValue::from_vec(vec![value]))?.notes,
+        };
+        let mut order = 1;
+        if let Some(options) = options {
+            validate_table_properties(&options, &["order"])?;
+            if let Some(value) = options.get::<_, Option<usize>>("order")? {
+                if value == 0 {
+                    return Err(bad_argument_error(
+                        "markov",
+                        "order",
+                        2,
+                        "order must be > 0",
+                    ));
+                }
+                // too large orders get clamped
+                order = value;
+            }
+        }
+        let events = notes.into_iter().map(Event::NoteEvents).collect();
+        let markov = MarkovEventIter::new(events, order, seed);
+        Ok(MarkovUserData { markov })
+    }
+}
+
+impl LuaUserData for MarkovUserData {}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        bindings::*,
+        event::{Event, NoteEvent},
+        note::Note,
+        rhythm::beat_time::BeatTimeRhythm,
+    };
+
+    #[test]
+    fn markov() -> LuaResult<()> {
+        // create a new engine and register bindings
+        let (mut lua, mut timeout_hook) = new_engine()?;
+        register_bindings(
+            &mut lua,
+            &timeout_hook,
+            &BeatTimeBase {
+                beats_per_min: 120.0,
+                beats_per_bar: 4,
+                samples_per_sec: 44100,
+            },
+        )?;
+
+        // reset timeout
+        timeout_hook.reset();
+
+        // invalid arguments
+        assert!(lua
+            .load(r#"markov(sequence("c4", "e4"), {order = 0})"#)
+            .exec()
+            .is_err());
+        assert!(lua
+            .load(r#"markov(sequence("c4", "e4"), {length = 1})"#)
+            .exec()
+            .is_err());
+        assert!(lua.load(r#"markov({"x4"})"#).exec().is_err());
+        // too large orders get clamped
+        assert!(lua
+            .load(r#"markov(sequence("c4", "e4"), {order = 1e12})"#)
+            .exec()
+            .is_ok());
+
+        // markov chains as emitter
+        let run_notes = || -> LuaResult<Vec<Note>> {
+            let rhythm = lua
+                .load(
+                    r#"
+                    math.randomseed(1234)
+                    return rhythm {
+                        emit = markov(sequence("c4", "e4", "g4", "e4", "c4", "g4"), {order = 1})
+                    }
+                "#,
+                )
+                .eval::<LuaValue>()?;
+            let rhythm = rhythm
+                .as_userdata()
+                .ok_or(LuaError::RuntimeError("No user data".to_string()))?
+                .borrow::<BeatTimeRhythm>()?
+                .clone();
+            Ok(rhythm
+                .take(16)
+                .map(|item| match item.event {
+                    Some(Event::NoteEvents(note_events)) => note_events
+                        .into_iter()
+                        .flatten()
+                        .map(|note_event: NoteEvent| note_event.note)
+                        .collect::<Vec<_>>(),
+                    _ => vec![],
+                })
+                .collect::<Vec<_>>()
+                .concat())
+        };
+        let notes = run_notes()?;
+        assert_eq!(notes.len(), 16);
+        assert_eq!(notes[0], Note::C4);
+        assert!(notes
+            .iter()
+            .all(|note| [Note::C4, Note::E4, Note::G4].contains(note)));
+        // seeded chains are reproducible
+        assert_eq!(notes, run_notes()?);
+        Ok(())
+    }
+}
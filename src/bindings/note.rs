@@ -2,9 +2,10 @@ use mlua::prelude::*;
 
 use super::unwrap::{
     amplify_array_from_value, bad_argument_error, chord_events_from_intervals,
-    chord_events_from_mode, delay_array_from_value, instrument_array_from_value,
-    note_events_from_value, panning_array_from_value, sequence_from_value,
-    transpose_steps_array_from_value, volume_array_from_value,
+    chord_events_from_mode, delay_array_from_value, glide_array_from_value,
+    instrument_array_from_value, length_array_from_value, note_events_from_value,
+    panning_array_from_value, sequence_from_value, transpose_steps_array_from_value,
+    volume_array_from_value,
 };
 
 use crate::{
@@ -193,6 +194,26 @@ impl LuaUserData for NoteUserData {
             }
             Ok(this.clone())
         });
+
+        methods.add_method_mut("with_length", |lua, this, value: LuaValue| {
+            let lengths = length_array_from_value(lua, value, this.notes.len())?;
+            for (note, length) in this.notes.iter_mut().zip(lengths) {
+                if let Some(note) = note {
+                    note.length = Some(length);
+                }
+            }
+            Ok(this.clone())
+        });
+
+        methods.add_method_mut("with_glide", |lua, this, value: LuaValue| {
+            let glides = glide_array_from_value(lua, value, this.notes.len())?;
+            for (note, glide) in this.notes.iter_mut().zip(glides) {
+                if let Some(note) = note {
+                    note.glide = Some(glide);
+                }
+            }
+            Ok(this.clone())
+        });
     }
 }
 
@@ -201,7 +222,10 @@ impl LuaUserData for NoteUserData {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{bindings::*, event::new_note};
+    use crate::{
+        bindings::*,
+        event::{new_note, ParameterId},
+    };
 
     fn new_test_engine() -> LuaResult<(Lua, LuaTimeoutHook)> {
         let (mut lua, mut timeout_hook) = new_engine()?;
@@ -520,4 +544,126 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn note_parameters() -> LuaResult<()> {
+        let (lua, _) = new_test_engine()?;
+
+        assert!(evaluate_note_userdata(&lua, r#"note({key = "c4", [74] = "x"})"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note({key = "c4", [-1] = 0.5})"#).is_err());
+        let note_event = |parameters: Vec<(usize, f32)>| {
+            Some(NoteEvent {
+                parameters: parameters
+                    .into_iter()
+                    .map(|(id, value)| (ParameterId::from(id), value))
+                    .collect(),
+                ..NoteEvent::from("c4")
+            })
+        };
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note({key = "c4", [1] = 0.25, [74] = 1})"#)?.notes,
+            vec![note_event(vec![(1, 0.25), (74, 1.0)])]
+        );
+        // parameters are passed back to lua as integer keys
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note(note({key = "c4", [74] = 0.5}).notes)"#)?.notes,
+            vec![note_event(vec![(74, 0.5)])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn note_length() -> LuaResult<()> {
+        let (lua, _) = new_test_engine()?;
+
+        // note strings and tables
+        assert!(evaluate_note_userdata(&lua, r#"note("c4 l-1")"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4 lx")"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4 l")"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4'maj l")"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note({key = "c4", length = -1})"#).is_err());
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note("c4", "d4 l0.5", "e4'maj l2")"#)?.notes,
+            vec![
+                new_note(("c4", None, 1.0, 0.0, 0.0, None)),
+                new_note(("d4", None, 1.0, 0.0, 0.0, Some(0.5))),
+                new_note(("e4", None, 1.0, 0.0, 0.0, Some(2.0))),
+                new_note(("g#4", None, 1.0, 0.0, 0.0, Some(2.0))),
+                new_note(("b4", None, 1.0, 0.0, 0.0, Some(2.0))),
+            ]
+        );
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note({key = "c4", length = 0.25})"#)?.notes,
+            vec![new_note(("c4", None, 1.0, 0.0, 0.0, Some(0.25)))]
+        );
+        assert_eq!(
+            lua.load(r#"return note("c4 l0.25").notes[1].length"#)
+                .eval::<f32>()?,
+            0.25
+        );
+
+        // with_length
+        assert!(evaluate_note_userdata(&lua, r#"note("c4"):with_length(1.0)"#).is_ok());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4"):with_length()"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4"):with_length(-1)"#).is_err());
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note("c4", "d4", "e4"):with_length({0.5, 2})"#)?.notes,
+            vec![
+                new_note(("c4", None, 1.0, 0.0, 0.0, Some(0.5))),
+                new_note(("d4", None, 1.0, 0.0, 0.0, Some(2.0))),
+                new_note(("e4", None, 1.0, 0.0, 0.0)),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn note_glide() -> LuaResult<()> {
+        let (lua, _) = new_test_engine()?;
+
+        let glide_note = |note: &str, glide: Option<f32>| {
+            new_note((note, None, 1.0, 0.0, 0.0)).map(|note| NoteEvent { glide, ..note })
+        };
+
+        // note strings and tables
+        assert!(evaluate_note_userdata(&lua, r#"note("c4 g-1")"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4 gx")"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4 g")"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note({key = "c4", glide = -1})"#).is_err());
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note("c4", "d4 g0.5", "e4'maj g1")"#)?.notes,
+            vec![
+                glide_note("c4", None),
+                glide_note("d4", Some(0.5)),
+                glide_note("e4", Some(1.0)),
+                glide_note("g#4", Some(1.0)),
+                glide_note("b4", Some(1.0)),
+            ]
+        );
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note({key = "c4", glide = 0.25})"#)?.notes,
+            vec![glide_note("c4", Some(0.25))]
+        );
+        assert_eq!(
+            lua.load(r#"return note("c4 g0.25").notes[1].glide"#)
+                .eval::<f32>()?,
+            0.25
+        );
+
+        // with_glide
+        assert!(evaluate_note_userdata(&lua, r#"note("c4"):with_glide(1.0)"#).is_ok());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4"):with_glide()"#).is_err());
+        assert!(evaluate_note_userdata(&lua, r#"note("c4"):with_glide(-1)"#).is_err());
+        assert_eq!(
+            evaluate_note_userdata(&lua, r#"note("c4", "d4", "e4"):with_glide({0.5, 2})"#)?.notes,
+            vec![
+                glide_note("c4", Some(0.5)),
+                glide_note("d4", Some(2.0)),
+                glide_note("e4", None),
+            ]
+        );
+
+        Ok(())
+    }
 }
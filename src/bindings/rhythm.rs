@@ -54,6 +54,8 @@ pub(crate) fn rhythm_from_userdata(
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeMap;
+
     use crate::{
         bindings::*,
         event::{Event, NoteEvent},
@@ -142,7 +144,10 @@ mod test {
                     note: Note::C6,
                     volume: 1.0,
                     panning: 0.0,
-                    delay: 0.0
+                    delay: 0.0,
+                    length: None,
+                    glide: None,
+                    parameters: BTreeMap::new()
                 })])),
                 duration: 11025
             })
@@ -219,7 +224,10 @@ mod test {
                     note: Note::C4,
                     volume: 1.0,
                     panning: 0.0,
-                    delay: 0.0
+                    delay: 0.0,
+                    length: None,
+                    glide: None,
+                    parameters: BTreeMap::new()
                 })])),
                 duration: 11025,
             })
@@ -321,7 +329,10 @@ mod test {
                     note: Note::C4,
                     volume: 1.0,
                     panning: 0.0,
-                    delay: 0.0
+                    delay: 0.0,
+                    length: None,
+                    glide: None,
+                    parameters: BTreeMap::new()
                 })],),),
                 duration: 48
             })
@@ -152,6 +152,64 @@ mod test {
                 .collect::<Vec<i32>>(),
             vec![60, 63, 65, 67, 70]
         );
+
+        // Scale (note, scala_file)
+        assert!(lua
+            .load(r#"scale("c", "missing.scl")"#)
+            .eval::<LuaValue>()
+            .is_err());
+        let scala_file = std::env::temp_dir().join("afseq_scale_test_5edo.scl");
+        std::fs::write(&scala_file, "5-EDO\n5\n240.0\n480.0\n720.0\n960.0\n2/1\n")?;
+        lua.globals()
+            .set("scala_file", scala_file.to_string_lossy().to_string())?;
+        let notes = lua
+            .load(r#"scale("c5", scala_file).notes"#)
+            .eval::<Vec<LuaValue>>();
+        std::fs::remove_file(&scala_file)?;
+        assert_eq!(
+            notes?
+                .iter()
+                .map(|v| v.as_i32().unwrap())
+                .collect::<Vec<i32>>(),
+            vec![60, 61, 62, 63, 64]
+        );
+
+        // Scale (note, scala_file, mapping_file)
+        let scala_file = std::env::temp_dir().join("afseq_scale_test_just.scl");
+        let mapping_file = std::env::temp_dir().join("afseq_scale_test_white.kbm");
+        std::fs::write(&scala_file, "just\n7\n9/8\n5/4\n4/3\n3/2\n5/3\n15/8\n2/1\n")?;
+        std::fs::write(
+            &mapping_file,
+            "12\n0\n127\n60\n69\n440.0\n7\n0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\n6\n",
+        )?;
+        lua.globals()
+            .set("scala_file", scala_file.to_string_lossy().to_string())?;
+        lua.globals()
+            .set("mapping_file", mapping_file.to_string_lossy().to_string())?;
+        let notes = lua
+            .load(r#"scale("d5", scala_file, mapping_file).notes"#)
+            .eval::<Vec<LuaValue>>();
+        let unmapped_root = lua
+            .load(r#"scale("c#5", scala_file, mapping_file)"#)
+            .eval::<LuaValue>();
+        let missing_mapping = lua
+            .load(r#"scale("c5", scala_file, "missing.kbm")"#)
+            .eval::<LuaValue>();
+        std::fs::remove_file(&scala_file)?;
+        std::fs::remove_file(&mapping_file)?;
+        assert_eq!(
+            notes?
+                .iter()
+                .map(|v| v.as_i32().unwrap())
+                .collect::<Vec<i32>>(),
+            vec![62, 64, 65, 67, 69, 71, 72]
+        );
+        assert!(unmapped_root.is_err());
+        assert!(missing_mapping.is_err());
+        assert!(lua
+            .load(r#"scale("c5", "minor", "missing.kbm")"#)
+            .eval::<LuaValue>()
+            .is_err());
         Ok(())
     }
 
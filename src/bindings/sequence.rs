@@ -1,9 +1,10 @@
 use mlua::prelude::*;
 
 use super::unwrap::{
-    amplify_array_from_value, bad_argument_error, delay_array_from_value,
-    instrument_array_from_value, note_events_from_value, panning_array_from_value,
-    sequence_from_value, transpose_steps_array_from_value, volume_array_from_value,
+    amplify_array_from_value, bad_argument_error, delay_array_from_value, glide_array_from_value,
+    instrument_array_from_value, length_array_from_value, note_events_from_value,
+    panning_array_from_value, sequence_from_value, transpose_steps_array_from_value,
+    volume_array_from_value,
 };
 
 use crate::{
@@ -175,6 +176,26 @@ impl LuaUserData for SequenceUserData {
             }
             Ok(this.clone())
         });
+
+        methods.add_method_mut("with_length", |lua, this, value: LuaValue| {
+            let lengths = length_array_from_value(lua, value, this.notes.len())?;
+            for (notes, length) in this.notes.iter_mut().zip(lengths) {
+                for note in notes.iter_mut().flatten() {
+                    note.length = Some(length);
+                }
+            }
+            Ok(this.clone())
+        });
+
+        methods.add_method_mut("with_glide", |lua, this, value: LuaValue| {
+            let glides = glide_array_from_value(lua, value, this.notes.len())?;
+            for (notes, glide) in this.notes.iter_mut().zip(glides) {
+                for note in notes.iter_mut().flatten() {
+                    note.glide = Some(glide);
+                }
+            }
+            Ok(this.clone())
+        });
     }
 }
 
@@ -340,6 +361,11 @@ mod test {
             r#"sequence({key = "c"}, "d", "f"):with_delay(0.0)"#
         )
         .is_ok());
+        assert!(evaluate_sequence_userdata(
+            &lua,
+            r#"sequence({key = "c"}, "d", "f"):with_length(0.5)"#
+        )
+        .is_ok());
         assert!(evaluate_sequence_userdata(
             &lua, //
             r#"sequence("c", "d", "f"):transposed({1, 2})"#
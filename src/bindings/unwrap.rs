@@ -1,12 +1,16 @@
 //! Various lua->rust conversion helpers
 
-use std::{ops::RangeBounds, sync::Arc};
+use std::{collections::BTreeMap, ops::RangeBounds, sync::Arc};
 
 use mlua::prelude::*;
 
+#[cfg(feature = "tidal")]
+use crate::bindings::cycle::CycleUserData;
+
 use crate::{
     bindings::{
-        callback::LuaCallbackFactory, note::NoteUserData, sequence::SequenceUserData,
+        arpeggiator::ArpeggiatorUserData, callback::LuaCallbackFactory, markov::MarkovUserData,
+        note::NoteUserData, sequence::SequenceUserData, weighted::WeightedChoiceUserData,
         LuaTimeoutHook,
     },
     prelude::*,
@@ -102,6 +106,18 @@ impl<'lua> IntoLua<'lua> for NoteEvent {
         table.set("volume", self.volume as f64)?;
         table.set("panning", self.panning as f64)?;
         table.set("delay", self.delay as f64)?;
+        if let Some(length) = self.length {
+            table.set("length", length as f64)?;
+        }
+        if let Some(glide) = self.glide {
+            table.set("glide", glide as f64)?;
+        }
+        for (parameter, value) in self.parameters {
+            table.set(
+                LuaInteger::try_from(usize::from(parameter)).unwrap_or(LuaInteger::MAX),
+                value as f64,
+            )?;
+        }
         Ok(LuaValue::Table(table))
     }
 }
@@ -123,6 +139,10 @@ pub(crate) fn sequence_from_value<'lua>(
 pub(crate) fn sequence_from_table<'lua>(
     table: &'lua LuaTable<'lua>,
 ) -> Option<Vec<LuaValue<'lua>>> {
+    // note tables may contain integer keys as custom parameters
+    if table.contains_key("key").unwrap_or(false) {
+        return None;
+    }
     let sequence = table
         .clone()
         .sequence_values::<LuaValue>()
@@ -249,6 +269,22 @@ pub(crate) fn delay_array_from_value(
     float_array_from_value(lua, value, array_len, "delay", 0.0..=1.0)
 }
 
+pub(crate) fn length_array_from_value(
+    lua: &Lua,
+    value: LuaValue,
+    array_len: usize,
+) -> LuaResult<Vec<f32>> {
+    float_array_from_value(lua, value, array_len, "length", 0.0..)
+}
+
+pub(crate) fn glide_array_from_value(
+    lua: &Lua,
+    value: LuaValue,
+    array_len: usize,
+) -> LuaResult<Vec<f32>> {
+    float_array_from_value(lua, value, array_len, "glide", 0.0..)
+}
+
 // ---------------------------------------------------------------------------------------------
 
 fn float_value_from_table<Range>(
@@ -318,6 +354,52 @@ pub(crate) fn delay_value_from_table(table: &LuaTable) -> LuaResult<f32> {
     float_value_from_table(table, "delay", 0.0..1.0, 0.0)
 }
 
+// Custom note parameters are integer keys with number values, e.g. `{ key = "c4", [74] = 0.5 }`.
+pub(crate) fn parameter_values_from_table(
+    table: &LuaTable,
+) -> LuaResult<BTreeMap<ParameterId, f32>> {
+    let mut parameters = BTreeMap::new();
+    for (key, value) in table.clone().pairs::<LuaValue, LuaValue>().flatten() {
+        if let Some(key) = key.as_integer() {
+            if key < 0 {
+                return Err(LuaError::RuntimeError(format!(
+                    "parameter id must be >= 0 but is '{}'",
+                    key
+                )));
+            }
+            if let Some(value) = value
+                .as_number()
+                .or(value.as_integer().map(|i| i as LuaNumber))
+            {
+                parameters.insert(ParameterId::from(key as usize), value as f32);
+            } else {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "number",
+                    message: Some(format!("parameter '{}' value must be a number", key)),
+                });
+            }
+        }
+    }
+    Ok(parameters)
+}
+
+pub(crate) fn length_value_from_table(table: &LuaTable) -> LuaResult<Option<f32>> {
+    if table.contains_key("length")? {
+        Ok(Some(float_value_from_table(table, "length", 0.0.., 0.0)?))
+    } else {
+        Ok(None)
+    }
+}
+
+pub(crate) fn glide_value_from_table(table: &LuaTable) -> LuaResult<Option<f32>> {
+    if table.contains_key("glide")? {
+        Ok(Some(float_value_from_table(table, "glide", 0.0.., 0.0)?))
+    } else {
+        Ok(None)
+    }
+}
+
 fn float_value_from_string<Range>(
     str: &str,
     name: &'static str,
@@ -385,6 +467,28 @@ pub(crate) fn delay_value_from_string(str: &str) -> LuaResult<f32> {
     float_value_from_string(str, "delay", 0.0..1.0, 0.0)
 }
 
+pub(crate) fn length_value_from_string(str: &str) -> LuaResult<f32> {
+    if str.is_empty() {
+        // unlike other properties, lengths have no default value
+        Err(LuaError::RuntimeError(
+            "length property is missing a value".to_string(),
+        ))
+    } else {
+        float_value_from_string(str, "length", 0.0.., 0.0)
+    }
+}
+
+pub(crate) fn glide_value_from_string(str: &str) -> LuaResult<f32> {
+    if str.is_empty() {
+        // unlike other properties, glides have no default value
+        Err(LuaError::RuntimeError(
+            "glide property is missing a value".to_string(),
+        ))
+    } else {
+        float_value_from_string(str, "glide", 0.0.., 0.0)
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 
 pub(crate) fn is_empty_note_string(s: &str) -> bool {
@@ -448,6 +552,8 @@ pub(crate) fn note_event_from_string(str: &str) -> LuaResult<Option<NoteEvent>>
         let mut volume = 1.0;
         let mut panning = 0.0;
         let mut delay = 0.0;
+        let mut length = None;
+        let mut glide = None;
         for split in white_space_splits {
             if let Some(instrument_str) = split.strip_prefix('#') {
                 instrument = instrument_value_from_string(instrument_str)?;
@@ -457,14 +563,22 @@ pub(crate) fn note_event_from_string(str: &str) -> LuaResult<Option<NoteEvent>>
                 panning = panning_value_from_string(panning_str)?;
             } else if let Some(delay_str) = split.strip_prefix('d') {
                 delay = delay_value_from_string(delay_str)?;
+            } else if let Some(length_str) = split.strip_prefix('l') {
+                length = Some(length_value_from_string(length_str)?);
+            } else if let Some(glide_str) = split.strip_prefix('g') {
+                glide = Some(glide_value_from_string(glide_str)?);
             } else {
                 return Err(LuaError::RuntimeError(
                     format!("invalid note string segment: '{}'. ", split) +
-                        "expecting only number values with '#' (instrument),'v' (volume), 'p' (panning) or 'd' (delay) prefixes here."),
+                        "expecting only number values with '#' (instrument),'v' (volume), 'p' (panning), 'd' (delay), 'l' (length) or 'g' (glide) prefixes here."),
                 );
             }
         }
-        Ok(new_note((note, instrument, volume, panning, delay)))
+        let note_event = new_note((note, instrument, volume, panning, delay, length));
+        Ok(note_event.map(|note_event| NoteEvent {
+            glide,
+            ..note_event
+        }))
     }
 }
 
@@ -482,28 +596,37 @@ pub(crate) fn note_event_from_table_map(table: &LuaTable) -> LuaResult<Option<No
         let volume = volume_value_from_table(table)?;
         let panning = panning_value_from_table(table)?;
         let delay = delay_value_from_table(table)?;
-        // { key = 60, [volume = 1.0, panning = 0.0, delay = 0.0] }
-        if let Some(note_value) = key.as_i32() {
-            Ok(new_note((
+        let length = length_value_from_table(table)?;
+        let glide = glide_value_from_table(table)?;
+        let parameters = parameter_values_from_table(table)?;
+        // { key = 60, [volume = 1.0, panning = 0.0, delay = 0.0, length = nil, glide = nil, [74] = 0.5] }
+        let note_event = if let Some(note_value) = key.as_i32() {
+            new_note((
                 Note::from(note_value as u8),
                 instrument,
                 volume,
                 panning,
                 delay,
-            )))
+                length,
+            ))
         }
-        // { key = "C4", [instrument = 1, volume = 1.0, panning = 0.0, delay = 0.0] }
+        // { key = "C4", [instrument = 1, volume = 1.0, panning = 0.0, delay = 0.0, length = nil, glide = nil] }
         else if let Some(note_str) = key.as_str() {
             let note =
                 Note::try_from(note_str).map_err(|err| LuaError::RuntimeError(err.to_string()))?;
-            Ok(new_note((note, instrument, volume, panning, delay)))
+            new_note((note, instrument, volume, panning, delay, length))
         } else {
-            Err(LuaError::FromLuaConversionError {
+            return Err(LuaError::FromLuaConversionError {
                 from: key.type_name(),
                 to: "note",
                 message: Some("invalid 'key' property in note table".to_string()),
-            })
-        }
+            });
+        };
+        Ok(note_event.map(|note_event| NoteEvent {
+            glide,
+            parameters,
+            ..note_event
+        }))
     }
 }
 
@@ -601,6 +724,8 @@ pub(crate) fn chord_events_from_string(chord_string: &str) -> LuaResult<Vec<Opti
     let mut volume = 1.0;
     let mut panning = 0.0;
     let mut delay = 0.0;
+    let mut length = None;
+    let mut glide = None;
     for split in white_space_splits {
         if let Some(instrument_str) = split.strip_prefix('#') {
             instrument = instrument_value_from_string(instrument_str)?;
@@ -610,10 +735,14 @@ pub(crate) fn chord_events_from_string(chord_string: &str) -> LuaResult<Vec<Opti
             panning = panning_value_from_string(panning_str)?;
         } else if let Some(delay_str) = split.strip_prefix('d') {
             delay = delay_value_from_string(delay_str)?;
+        } else if let Some(length_str) = split.strip_prefix('l') {
+            length = Some(length_value_from_string(length_str)?);
+        } else if let Some(glide_str) = split.strip_prefix('g') {
+            glide = Some(glide_value_from_string(glide_str)?);
         } else {
             return Err(LuaError::RuntimeError(
                     format!("invalid note string segment: '{}'. ", split) +
-                        "expecting only number values with '#' (instrument),'v' (volume), 'p' (panning) or 'd' (delay) prefixes here."),
+                        "expecting only number values with '#' (instrument),'v' (volume), 'p' (panning), 'd' (delay), 'l' (length) or 'g' (glide) prefixes here."),
                 );
         }
     }
@@ -627,7 +756,12 @@ pub(crate) fn chord_events_from_string(chord_string: &str) -> LuaResult<Vec<Opti
                 volume,
                 panning,
                 delay,
+                length,
             ))
+            .map(|note_event| NoteEvent {
+                glide,
+                ..note_event
+            })
         })
         .collect::<Vec<_>>())
 }
@@ -794,12 +928,26 @@ pub(crate) fn event_iter_from_value(
 ) -> LuaResult<Box<dyn EventIter>> {
     match value {
         LuaValue::UserData(userdata) => {
-            if userdata.is::<NoteUserData>() {
+            #[cfg(feature = "tidal")]
+            if userdata.is::<CycleUserData>() {
+                let cycle = userdata.borrow::<CycleUserData>()?;
+                return Ok(Box::new(cycle.cycle.clone()));
+            }
+            if userdata.is::<ArpeggiatorUserData>() {
+                let arp = userdata.borrow::<ArpeggiatorUserData>()?;
+                Ok(arp.arpeggiator.duplicate())
+            } else if userdata.is::<MarkovUserData>() {
+                let markov = userdata.borrow::<MarkovUserData>()?;
+                Ok(Box::new(markov.markov.clone()))
+            } else if userdata.is::<NoteUserData>() {
                 let note = userdata.borrow::<NoteUserData>()?;
                 Ok(Box::new(note.notes.clone().to_event()))
             } else if userdata.is::<SequenceUserData>() {
                 let sequence = userdata.borrow::<SequenceUserData>()?;
                 Ok(Box::new(sequence.notes.clone().to_event_sequence()))
+            } else if userdata.is::<WeightedChoiceUserData>() {
+                let weighted = userdata.borrow::<WeightedChoiceUserData>()?;
+                Ok(Box::new(weighted.choice.clone()))
             } else {
                 Err(LuaError::FromLuaConversionError {
                     from: "userdata",
@@ -0,0 +1,137 @@
+use mlua::prelude::*;
+
+use super::unwrap::{bad_argument_error, note_events_from_value};
+
+use crate::event::{weighted::WeightedChoiceEventIter, Event};
+
+// ---------------------------------------------------------------------------------------------
+
+// WeightedChoice
+#[derive(Clone, Debug)]
+pub struct WeightedChoiceUserData {
+    pub choice: WeightedChoiceEventIter,
+}
+
+impl WeightedChoiceUserData {
+    pub fn from(choices: LuaTable, seed: Option<[u8; 32]>) -> LuaResult<Self> {
+        let choice_error = |index: usize, message: &str| {
+            bad_argument_error(
+                "choose",
+                "choices",
+                1,
+                &format!("invalid choice #{}: {}", index, message),
+            )
+        };
+        let mut weighted_events = Vec::new();
+        for (index, choice) in choices.sequence_values::<LuaValue>().enumerate() {
+            // { note_value, weight }
+            let choice = choice?;
+            let choice = choice
+                .as_table()
+                .ok_or_else(|| choice_error(index + 1, "expecting a {note, weight} table"))?;
+            if choice.raw_len() != 2 {
+                return Err(choice_error(index + 1, "expecting a {note, weight} table"));
+            }
+            let value = choice.raw_get::<_, LuaValue>(1)?;
+            let weight = match choice.raw_get::<_, LuaValue>(2)? {
+                LuaValue::Integer(weight) => weight as f32,
+                LuaValue::Number(weight) => weight as f32,
+                _ => return Err(choice_error(index + 1, "weight must be a number")),
+            };
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(choice_error(index + 1, "weight must be >= 0"));
+            }
+            let notes = note_events_from_value(&value, Some(index))?;
+            weighted_events.push((Event::NoteEvents(notes), weight));
+        }
+        let total_weight: f32 = weighted_events.iter().map(|(_, weight)| weight).sum();
+        if !total_weight.is_finite() {
+            return Err(bad_argument_error(
+                "choose",
+                "choices",
+                1,
+                "the sum of all weights must be finite",
+            ));
+        }
+        let choice = WeightedChoiceEventIter::new(weighted_events, seed);
+        Ok(WeightedChoiceUserData { choice })
+    }
+}
+
+impl LuaUserData for WeightedChoiceUserData {}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        bindings::*,
+        event::{Event, NoteEvent},
+        note::Note,
+        rhythm::beat_time::BeatTimeRhythm,
+    };
+
+    #[test]
+    fn weighted_choice() -> LuaResult<()> {
+        // create a new engine and register bindings
+        let (mut lua, mut timeout_hook) = new_engine()?;
+        register_bindings(
+            &mut lua,
+            &timeout_hook,
+            &BeatTimeBase {
+                beats_per_min: 120.0,
+                beats_per_bar: 4,
+                samples_per_sec: 44100,
+            },
+        )?;
+
+        // reset timeout
+        timeout_hook.reset();
+
+        // invalid arguments
+        assert!(lua.load(r#"choose()"#).exec().is_err());
+        assert!(lua.load(r#"choose({"c4", "e4"})"#).exec().is_err());
+        assert!(lua.load(r#"choose({{"c4"}})"#).exec().is_err());
+        assert!(lua.load(r#"choose({{"c4", "x"}})"#).exec().is_err());
+        assert!(lua.load(r#"choose({{"c4", -1}})"#).exec().is_err());
+        assert!(lua.load(r#"choose({{"x4", 1}})"#).exec().is_err());
+        assert!(lua
+            .load(r#"choose({{"c4", 3e38}, {"e4", 3e38}})"#)
+            .exec()
+            .is_err());
+
+        // weighted choices as emitter
+        let rhythm = lua
+            .load(
+                r#"
+                math.randomseed(1234)
+                return rhythm {
+                    emit = choose({ {"c4", 5}, {"e4", 3}, {note("g4"), 2}, {"b4", 0} })
+                }
+            "#,
+            )
+            .eval::<LuaValue>()?;
+        let rhythm = rhythm
+            .as_userdata()
+            .ok_or(LuaError::RuntimeError("No user data".to_string()))?
+            .borrow::<BeatTimeRhythm>()?
+            .clone();
+        let notes = rhythm
+            .take(32)
+            .map(|item| match item.event {
+                Some(Event::NoteEvents(note_events)) => note_events
+                    .into_iter()
+                    .flatten()
+                    .map(|note_event: NoteEvent| note_event.note)
+                    .collect::<Vec<_>>(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        assert_eq!(notes.len(), 32);
+        assert!(notes
+            .iter()
+            .all(|note| [Note::C4, Note::E4, Note::G4].contains(note)));
+        Ok(())
+    }
+}
@@ -1,22 +1,30 @@
 //! Events and event iterators which get emitted by a `Rhythm`.
 
-use crate::{BeatTimeBase, Note, PulseIterItem};
+use crate::{BeatTimeBase, Note, PulseIterItem, SampleTime};
 use fixed::{FixedEventIter, ToFixedEventIter, ToFixedEventIterSequence};
 
 use derive_more::{Deref, Display, From, Into};
 
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     fmt::Debug,
     fmt::Display,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
+pub mod arpeggiator;
+#[cfg(feature = "tidal")]
+pub mod cycle;
 pub mod empty;
 pub mod fixed;
+pub mod layered;
+pub mod mapped;
+pub mod markov;
 pub mod mutated;
 #[cfg(feature = "scripting")]
 pub mod scripted;
+pub mod weighted;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -24,8 +32,9 @@ pub mod scripted;
 #[derive(Copy, Clone, Debug, Display, Deref, From, Into, PartialEq, Eq, Hash)]
 pub struct InstrumentId(usize);
 
-/// Id to refer to a specific parameter in a [`ParameterChangeEvent`].
-#[derive(Copy, Clone, Debug, Display, Deref, From, Into, PartialEq, Eq, Hash)]
+/// Id to refer to a specific parameter in a [`ParameterChangeEvent`] or in the custom
+/// parameters of a [`NoteEvent`].
+#[derive(Copy, Clone, Debug, Display, Deref, From, Into, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ParameterId(usize);
 
 // -------------------------------------------------------------------------------------------------
@@ -43,16 +52,20 @@ pub fn unique_instrument_id() -> InstrumentId {
 pub struct NoteEvent {
     pub note: Note,
     pub instrument: Option<InstrumentId>,
-    pub volume: f32,  // [0 - INF]
-    pub panning: f32, // [-1 - 1]
-    pub delay: f32,   // [0 - 1]
+    pub volume: f32,         // [0 - INF]
+    pub panning: f32,        // [-1 - 1]
+    pub delay: f32,          // [0 - 1]
+    pub length: Option<f32>, // [0 - INF] relative to the step, None plays until the next note
+    pub glide: Option<f32>,  // [0 - INF] relative to the step, None retriggers playing notes
+    // custom per-note parameter values, e.g. a filter cutoff or sample start
+    pub parameters: BTreeMap<ParameterId, f32>,
 }
 
 impl NoteEvent {
     pub fn to_string(&self, show_instruments: bool) -> String {
         if show_instruments {
             format!(
-                "{} {} {:.2} {:.2} {:.2}",
+                "{} {} {:.2} {:.2} {:.2}{}",
                 self.note,
                 if let Some(instrument) = self.instrument {
                     format!("#{:02}", instrument)
@@ -61,7 +74,11 @@ impl NoteEvent {
                 },
                 self.volume,
                 self.panning,
-                self.delay
+                self.delay,
+                self.parameters
+                    .iter()
+                    .map(|(parameter, value)| format!(" {:02}:{:.3}", parameter, value))
+                    .collect::<String>()
             )
         } else {
             format!(
@@ -85,6 +102,9 @@ where
             volume: 1.0,
             panning: 0.0,
             delay: 0.0,
+            length: None,
+            glide: None,
+            parameters: BTreeMap::new(),
         }
     }
 }
@@ -103,6 +123,9 @@ where
             volume: 1.0,
             panning: 0.0,
             delay: 0.0,
+            length: None,
+            glide: None,
+            parameters: BTreeMap::new(),
         }
     }
 }
@@ -122,6 +145,9 @@ where
             volume,
             panning: 0.0,
             delay: 0.0,
+            length: None,
+            glide: None,
+            parameters: BTreeMap::new(),
         }
     }
 }
@@ -142,6 +168,9 @@ where
             volume,
             panning,
             delay: 0.0,
+            length: None,
+            glide: None,
+            parameters: BTreeMap::new(),
         }
     }
 }
@@ -163,6 +192,37 @@ where
             volume,
             panning,
             delay,
+            length: None,
+            glide: None,
+            parameters: BTreeMap::new(),
+        }
+    }
+}
+
+impl<N: TryInto<Note>, I: Into<Option<InstrumentId>>> From<(N, I, f32, f32, f32, Option<f32>)>
+    for NoteEvent
+where
+    <N as TryInto<Note>>::Error: std::fmt::Debug,
+{
+    // Initialize from a (Instrument, Note, Volume, Panning, Delay, Length) tuple
+    fn from(
+        (note, instrument, volume, panning, delay, length): (N, I, f32, f32, f32, Option<f32>),
+    ) -> Self {
+        let note = note.try_into().expect("Failed to convert note");
+        let instrument = instrument.into();
+        let volume = volume.clamp(0.0, 1.0);
+        let panning = panning.clamp(-1.0, 1.0);
+        let delay = delay.clamp(0.0, 1.0);
+        let length = length.map(|length| length.max(0.0));
+        Self {
+            note,
+            instrument,
+            volume,
+            panning,
+            delay,
+            length,
+            glide: None,
+            parameters: BTreeMap::new(),
         }
     }
 }
@@ -251,27 +311,114 @@ pub fn new_polyphonic_note_sequence_event<E: Into<NoteEvent>>(
 
 // -------------------------------------------------------------------------------------------------
 
+/// Curve shape of a [`ParameterRamp`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Display)]
+pub enum RampCurve {
+    /// Constant rate of change.
+    #[default]
+    #[display(fmt = "lin")]
+    Linear,
+    /// Slow start, fast end: useful for e.g. cutoff or pitch sweeps.
+    #[display(fmt = "exp")]
+    Exponential,
+    /// Slow start and end, fast middle.
+    #[display(fmt = "s")]
+    SCurve,
+}
+
+impl RampCurve {
+    /// Steepness of the exponential curve.
+    const EXPONENTIAL_STEEPNESS: f32 = 5.0;
+
+    /// Apply the curve on a linear position in range \[0 - 1\].
+    pub fn apply(&self, position: f32) -> f32 {
+        let position = position.clamp(0.0, 1.0);
+        match self {
+            RampCurve::Linear => position,
+            RampCurve::Exponential => {
+                let steepness = Self::EXPONENTIAL_STEEPNESS;
+                ((steepness * position).exp() - 1.0) / (steepness.exp() - 1.0)
+            }
+            RampCurve::SCurve => position * position * (3.0 - 2.0 * position),
+        }
+    }
+}
+
+/// Ramp from the value of a [`ParameterChangeEvent`] to a target value.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ParameterRamp {
+    pub target: f32,
+    pub duration: f32, // [0 - INF] relative to the step
+    pub curve: RampCurve,
+}
+
 /// Single parameter change event in a [`Event`].
+///
+/// Without a ramp, the parameter jumps to the event's value. With a ramp, the parameter starts
+/// at the event's value and moves to the ramp's target value within the ramp's duration. Use
+/// [`Self::value_at`] or [`Self::value_at_sample_time`] to interpolate the ramp.
 #[derive(Clone, PartialEq, Debug)]
 pub struct ParameterChangeEvent {
     pub parameter: Option<ParameterId>,
     pub value: f32,
+    pub ramp: Option<ParameterRamp>,
 }
 
 impl ParameterChangeEvent {
+    /// Parameter value at the given time, relative to the event's start in steps.
+    pub fn value_at(&self, time: f32) -> f32 {
+        match &self.ramp {
+            Some(ramp) => {
+                if ramp.duration <= 0.0 || time >= ramp.duration {
+                    ramp.target
+                } else if time <= 0.0 {
+                    self.value
+                } else {
+                    let position = ramp.curve.apply(time / ramp.duration);
+                    self.value + (ramp.target - self.value) * position
+                }
+            }
+            None => self.value,
+        }
+    }
+
+    /// Parameter value at the given sample time, for an event which got emitted at
+    /// `event_time` with a step duration of `event_duration` samples.
+    pub fn value_at_sample_time(
+        &self,
+        event_time: SampleTime,
+        event_duration: SampleTime,
+        sample_time: SampleTime,
+    ) -> f32 {
+        if event_duration == 0 {
+            return self.value_at(if sample_time > event_time {
+                f32::INFINITY
+            } else {
+                0.0
+            });
+        }
+        let time = sample_time.saturating_sub(event_time) as f64 / event_duration as f64;
+        self.value_at(time as f32)
+    }
+
     pub fn to_string(&self, show_parameter: bool) -> String {
+        let ramp = match &self.ramp {
+            Some(ramp) => format!(" -> {:.3} {} {:.2}", ramp.target, ramp.curve, ramp.duration),
+            None => String::new(),
+        };
         if show_parameter {
             format!(
-                "{} {:.3}",
+                "{} {:.3}{}",
                 if let Some(parameter) = self.parameter {
                     format!("{:02}", parameter)
                 } else {
                     "NA".to_string()
                 },
                 self.value,
+                ramp
             )
         } else {
-            format!("{:.3}", self.value)
+            format!("{:.3}{}", self.value, ramp)
         }
     }
 }
@@ -289,7 +436,12 @@ pub fn new_parameter_change<Parameter: Into<Option<ParameterId>>>(
     value: f32,
 ) -> ParameterChangeEvent {
     let parameter: Option<ParameterId> = parameter.into();
-    ParameterChangeEvent { parameter, value }
+    let ramp = None;
+    ParameterChangeEvent {
+        parameter,
+        value,
+        ramp,
+    }
 }
 
 /// Shortcut for creating a new [`ParameterChangeEvent`] [`EventIter`].
@@ -300,6 +452,233 @@ pub fn new_parameter_change_event<Parameter: Into<Option<ParameterId>>>(
     new_parameter_change(parameter, value).to_event()
 }
 
+/// Shortcut for creating a new [`ParameterChangeEvent`] which ramps from `value` to `target`
+/// within the given duration in steps.
+pub fn new_parameter_ramp<Parameter: Into<Option<ParameterId>>>(
+    parameter: Parameter,
+    value: f32,
+    target: f32,
+    duration: f32,
+    curve: RampCurve,
+) -> ParameterChangeEvent {
+    let parameter: Option<ParameterId> = parameter.into();
+    let duration = duration.max(0.0);
+    let ramp = Some(ParameterRamp {
+        target,
+        duration,
+        curve,
+    });
+    ParameterChangeEvent {
+        parameter,
+        value,
+        ramp,
+    }
+}
+
+/// Shortcut for creating a new parameter ramp [`EventIter`]. See [`new_parameter_ramp`].
+pub fn new_parameter_ramp_event<Parameter: Into<Option<ParameterId>>>(
+    parameter: Parameter,
+    value: f32,
+    target: f32,
+    duration: f32,
+    curve: RampCurve,
+) -> FixedEventIter {
+    new_parameter_ramp(parameter, value, target, duration, curve).to_event()
+}
+
+/// Shortcut for creating a parameter envelope [`EventIter`] from a start value and a list of
+/// `(target, duration, curve)` segments. Each segment is emitted as a ramp event, starting at
+/// the previous segment's target value. Segments which last more than one step are followed by
+/// empty hold steps, so the next segment starts when the previous one ended.
+///
+/// ### Example
+/// ```rust
+/// use afseq::prelude::*;
+///
+/// // attack, decay over two steps, then release to 0 over two steps
+/// let envelope = new_parameter_envelope_event(ParameterId::from(1), 0.0, vec![
+///     (1.0, 1.0, RampCurve::Linear),
+///     (0.5, 2.0, RampCurve::Exponential),
+///     (0.0, 2.0, RampCurve::SCurve),
+/// ]);
+/// ```
+///
+/// ### Panics
+/// Panics when the duration of a segment, except the last one, is not a whole number of steps
+/// >= 1.
+pub fn new_parameter_envelope_event<Parameter: Into<Option<ParameterId>>>(
+    parameter: Parameter,
+    value: f32,
+    segments: Vec<(f32, f32, RampCurve)>,
+) -> FixedEventIter {
+    let parameter: Option<ParameterId> = parameter.into();
+    let mut value = value;
+    let mut sequence = Vec::with_capacity(segments.len());
+    let segment_count = segments.len();
+    for (index, (target, duration, curve)) in segments.into_iter().enumerate() {
+        sequence.push(Event::ParameterChangeEvent(new_parameter_ramp(
+            parameter, value, target, duration, curve,
+        )));
+        if index + 1 < segment_count {
+            assert!(
+                duration >= 1.0 && duration.fract() == 0.0,
+                "Invalid envelope segment duration: duration must be a whole number of steps >= 1"
+            );
+            // hold until the segment's ramp finished
+            for _ in 1..duration as usize {
+                sequence.push(Event::NoteEvents(vec![]));
+            }
+        }
+        value = target;
+    }
+    FixedEventIter::new(sequence)
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Tempo and/or time signature change event in a [`Event`].
+///
+/// When emitted by a rhythm, a [`Phrase`](crate::Phrase) or [`Sequence`](crate::Sequence) applies
+/// the changed [`BeatTimeBase`] to all its rhythms at the exact sample time of the event.
+/// Unset values keep the current time base's value.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct TempoChangeEvent {
+    pub beats_per_min: Option<f32>,
+    pub beats_per_bar: Option<u32>,
+}
+
+impl TempoChangeEvent {
+    /// Maximum tempo of a tempo change. Faster tempos get clamped.
+    pub const MAX_BEATS_PER_MIN: f32 = 1000.0;
+
+    /// Apply the tempo and time signature change to the given time base.
+    ///
+    /// Invalid values, such as tempos which are not finite or not > 0, are ignored and keep the
+    /// time base's value. Tempos get clamped to [`Self::MAX_BEATS_PER_MIN`].
+    pub fn apply(&self, time_base: &BeatTimeBase) -> BeatTimeBase {
+        let beats_per_min = self
+            .beats_per_min
+            .filter(|bpm| bpm.is_finite() && *bpm > 0.0)
+            .map_or(time_base.beats_per_min, |bpm| {
+                bpm.min(Self::MAX_BEATS_PER_MIN)
+            });
+        let beats_per_bar = self
+            .beats_per_bar
+            .filter(|bpb| *bpb > 0)
+            .unwrap_or(time_base.beats_per_bar);
+        BeatTimeBase {
+            beats_per_min,
+            beats_per_bar,
+            samples_per_sec: time_base.samples_per_sec,
+        }
+    }
+}
+
+impl Display for TempoChangeEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let beats_per_min = match self.beats_per_min {
+            Some(beats_per_min) => format!("{:.2} bpm", beats_per_min),
+            None => "-- bpm".to_string(),
+        };
+        let beats_per_bar = match self.beats_per_bar {
+            Some(beats_per_bar) => format!("{} bpb", beats_per_bar),
+            None => "-- bpb".to_string(),
+        };
+        f.write_fmt(format_args!("{} {}", beats_per_min, beats_per_bar))
+    }
+}
+
+/// Shortcut for creating a new [`TempoChangeEvent`]. The tempo gets clamped to
+/// [`TempoChangeEvent::MAX_BEATS_PER_MIN`].
+///
+/// ### Panics
+/// Panics when the given tempo is not finite or not > 0, or beats per bar are not > 0.
+pub fn new_tempo_change<BeatsPerMin: Into<Option<f32>>, BeatsPerBar: Into<Option<u32>>>(
+    beats_per_min: BeatsPerMin,
+    beats_per_bar: BeatsPerBar,
+) -> TempoChangeEvent {
+    let beats_per_min = beats_per_min.into();
+    let beats_per_bar = beats_per_bar.into();
+    assert!(
+        beats_per_min.is_none_or(|bpm| bpm.is_finite() && bpm > 0.0),
+        "Invalid tempo: beats per minute must be a finite number > 0"
+    );
+    let beats_per_min = beats_per_min.map(|bpm| bpm.min(TempoChangeEvent::MAX_BEATS_PER_MIN));
+    assert!(
+        beats_per_bar.is_none_or(|bpb| bpb > 0),
+        "Invalid time signature: beats per bar must be > 0"
+    );
+    TempoChangeEvent {
+        beats_per_min,
+        beats_per_bar,
+    }
+}
+
+/// Shortcut for creating a new tempo change [`EventIter`]. See [`new_tempo_change`].
+///
+/// ### Panics
+/// Panics when the given tempo is not finite or not > 0, or beats per bar are not > 0.
+pub fn new_tempo_change_event<BeatsPerMin: Into<Option<f32>>, BeatsPerBar: Into<Option<u32>>>(
+    beats_per_min: BeatsPerMin,
+    beats_per_bar: BeatsPerBar,
+) -> FixedEventIter {
+    new_tempo_change(beats_per_min, beats_per_bar).to_event()
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Pitch bend event in a [`Event`].
+///
+/// Bends all playing and following notes of the emitting rhythm by the given amount of
+/// semitones, relative to their original pitch. An optional glide time, relative to the event's
+/// step duration, slides the pitch to its new value instead of changing it immediately.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct PitchBendEvent {
+    pub semitones: f32,     // [-INF - INF] bend amount in semitones
+    pub glide: Option<f32>, // [0 - INF] relative to the step, None applies the bend immediately
+}
+
+impl PitchBendEvent {
+    /// Playback speed factor of the bend, e.g. 2.0 for a bend of 12 semitones.
+    pub fn speed_factor(&self) -> f64 {
+        2.0_f64.powf(self.semitones as f64 / 12.0)
+    }
+}
+
+impl Display for PitchBendEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let glide = match self.glide {
+            Some(glide) => format!("{:.2}", glide),
+            None => "--".to_string(),
+        };
+        f.write_fmt(format_args!("bend {:+.2} st {}", self.semitones, glide))
+    }
+}
+
+/// Shortcut for creating a new [`PitchBendEvent`].
+///
+/// ### Panics
+/// Panics when the given glide time is not >= 0.
+pub fn new_pitch_bend<Glide: Into<Option<f32>>>(semitones: f32, glide: Glide) -> PitchBendEvent {
+    let glide = glide.into();
+    assert!(
+        glide.is_none_or(|glide| glide >= 0.0),
+        "Invalid glide: glide must be >= 0"
+    );
+    PitchBendEvent { semitones, glide }
+}
+
+/// Shortcut for creating a new pitch bend [`EventIter`]. See [`new_pitch_bend`].
+///
+/// ### Panics
+/// Panics when the given glide time is not >= 0.
+pub fn new_pitch_bend_event<Glide: Into<Option<f32>>>(
+    semitones: f32,
+    glide: Glide,
+) -> FixedEventIter {
+    new_pitch_bend(semitones, glide).to_event()
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// Event which gets emitted by an [`EventIter`].
@@ -307,6 +686,8 @@ pub fn new_parameter_change_event<Parameter: Into<Option<ParameterId>>>(
 pub enum Event {
     NoteEvents(Vec<Option<NoteEvent>>),
     ParameterChangeEvent(ParameterChangeEvent),
+    TempoChangeEvent(TempoChangeEvent),
+    PitchBendEvent(PitchBendEvent),
 }
 
 impl Event {
@@ -326,6 +707,8 @@ impl Event {
             Event::ParameterChangeEvent(change) => {
                 change.to_string(show_instruments_and_parameters)
             }
+            Event::TempoChangeEvent(change) => change.to_string(),
+            Event::PitchBendEvent(bend) => bend.to_string(),
         }
     }
 }
@@ -388,3 +771,127 @@ impl Iterator for dyn EventIter {
         self.run(pulse, pulse_pattern_length, emit_event)
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+
+/// Shared helpers for event iter tests.
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use super::{Event, EventIter, NoteEvent};
+    use crate::{Note, PulseIterItem};
+
+    /// Create a note event with a single note.
+    pub fn note_event(note: Note) -> Event {
+        Event::NoteEvents(vec![Some(NoteEvent::from(note))])
+    }
+
+    /// Run the given event iter `count` times with default pulses and collect its output.
+    pub fn run_events(event_iter: &mut dyn EventIter, count: usize) -> Vec<Option<Event>> {
+        let pulse = PulseIterItem::default();
+        (0..count).map(|_| event_iter.run(pulse, 1, true)).collect()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tempo_changes() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 44100,
+        };
+        let applied_tempo = |beats_per_min| {
+            TempoChangeEvent {
+                beats_per_min: Some(beats_per_min),
+                beats_per_bar: Some(0),
+            }
+            .apply(&time_base)
+        };
+        assert_eq!(applied_tempo(90.0).beats_per_min, 90.0);
+        assert_eq!(applied_tempo(90.0).beats_per_bar, 4);
+        assert_eq!(applied_tempo(f32::INFINITY).beats_per_min, 120.0);
+        assert_eq!(applied_tempo(-1.0).beats_per_min, 120.0);
+        assert_eq!(
+            applied_tempo(1e30).beats_per_min,
+            TempoChangeEvent::MAX_BEATS_PER_MIN
+        );
+
+        assert_eq!(
+            new_tempo_change(1e30, None).beats_per_min,
+            Some(TempoChangeEvent::MAX_BEATS_PER_MIN)
+        );
+        assert!(std::panic::catch_unwind(|| new_tempo_change(f32::INFINITY, None)).is_err());
+        assert!(std::panic::catch_unwind(|| new_tempo_change(0.0, None)).is_err());
+    }
+
+    #[test]
+    fn pitch_bends() {
+        assert_eq!(new_pitch_bend(0.0, None).speed_factor(), 1.0);
+        assert_eq!(new_pitch_bend(12.0, None).speed_factor(), 2.0);
+        assert_eq!(new_pitch_bend(-24.0, None).speed_factor(), 0.25);
+        assert!((new_pitch_bend(7.0, None).speed_factor() - 1.498307).abs() < 1e-6);
+        assert_eq!(new_pitch_bend(2.0, 0.5).to_string(), "bend +2.00 st 0.50");
+        assert_eq!(new_pitch_bend(-1.5, None).to_string(), "bend -1.50 st --");
+    }
+
+    #[test]
+    fn parameter_ramps() {
+        let jump = new_parameter_change(ParameterId(1), 0.5);
+        assert_eq!(jump.value_at(0.0), 0.5);
+        assert_eq!(jump.value_at(10.0), 0.5);
+
+        let linear = new_parameter_ramp(ParameterId(1), 0.0, 1.0, 2.0, RampCurve::Linear);
+        assert_eq!(linear.value_at(-1.0), 0.0);
+        assert_eq!(linear.value_at(1.0), 0.5);
+        assert_eq!(linear.value_at(2.0), 1.0);
+        assert_eq!(linear.value_at(3.0), 1.0);
+        assert_eq!(linear.value_at_sample_time(100, 200, 300), 0.5);
+        assert_eq!(linear.value_at_sample_time(100, 0, 50), 0.0);
+        assert_eq!(linear.value_at_sample_time(100, 0, 101), 1.0);
+
+        let exponential = new_parameter_ramp(ParameterId(1), 1.0, 0.0, 1.0, RampCurve::Exponential);
+        assert!(exponential.value_at(0.5) > 0.5);
+        let s_curve = new_parameter_ramp(ParameterId(1), 0.0, 1.0, 1.0, RampCurve::SCurve);
+        assert!(s_curve.value_at(0.25) < 0.25);
+        assert!((s_curve.value_at(0.5) - 0.5).abs() < f32::EPSILON);
+        assert!(s_curve.value_at(0.75) > 0.75);
+        assert_eq!(s_curve.to_string(true), "01 0.000 -> 1.000 s 1.00");
+
+        // envelopes hold segments which last more than one step
+        let mut envelope = new_parameter_envelope_event(
+            ParameterId(1),
+            0.0,
+            vec![
+                (1.0, 1.0, RampCurve::Linear),
+                (0.5, 2.0, RampCurve::Exponential),
+                (0.0, 2.0, RampCurve::SCurve),
+            ],
+        );
+        let pulse = PulseIterItem::default();
+        let mut values = vec![];
+        for _ in 0..5 {
+            match envelope.run(pulse, 1, true) {
+                Some(Event::ParameterChangeEvent(event)) => {
+                    values.push(Some((event.value, event.ramp.map(|ramp| ramp.target))))
+                }
+                Some(Event::NoteEvents(notes)) if notes.is_empty() => values.push(None),
+                event => panic!("unexpected event: {:?}", event),
+            }
+        }
+        assert_eq!(
+            values,
+            vec![
+                Some((0.0, Some(1.0))),
+                Some((1.0, Some(0.5))),
+                None,
+                Some((0.5, Some(0.0))),
+                Some((0.0, Some(1.0))),
+            ]
+        );
+    }
+}
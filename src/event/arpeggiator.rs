@@ -0,0 +1,437 @@
+use std::{borrow::Cow, fmt::Debug};
+
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::{
+    event::{Event, EventIter, NoteEvent},
+    BeatTimeBase, PulseIterItem,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Note order of an [`ArpeggiatorEventIter`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ArpeggiatorMode {
+    /// Lowest to highest note.
+    #[default]
+    Up,
+    /// Highest to lowest note.
+    Down,
+    /// Lowest to highest, then back down, without repeating the highest and lowest note.
+    UpDown,
+    /// Randomly picked notes.
+    Random,
+    /// Notes in the order they got emitted by the wrapped event iter.
+    AsPlayed,
+}
+
+impl ArpeggiatorMode {
+    /// Names of all modes, as accepted by `TryFrom<&str>`.
+    pub fn mode_names() -> Vec<&'static str> {
+        vec!["up", "down", "updown", "random", "played"]
+    }
+}
+
+impl TryFrom<&str> for ArpeggiatorMode {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            "updown" => Ok(Self::UpDown),
+            "random" => Ok(Self::Random),
+            "played" => Ok(Self::AsPlayed),
+            _ => Err(format!("invalid arpeggiator mode '{}'", value)),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Arpeggiates the notes of polyphonic note events, emitted by another [`EventIter`].
+///
+/// The wrapped event iter runs with each pulse. Note-ons of its note events become the set of
+/// held notes, which then get played one after another in the arpeggiator's mode, optionally
+/// repeated in higher octaves. Note-offs stop the arpeggiator, other events are passed as they are.
+///
+/// The arpeggiator's rate defines how many notes are played per pulse: with a rate of 2, two
+/// notes are played per pulse, with a rate of 0.5, a note is played every second pulse. Multiple
+/// notes within a single pulse are emitted as one polyphonic note event with note delays.
+#[derive(Debug)]
+pub struct ArpeggiatorEventIter {
+    event_iter: Box<dyn EventIter>,
+    mode: ArpeggiatorMode,
+    octaves: usize,
+    rate: f32,
+    notes: Vec<NoteEvent>,
+    note_index: usize,
+    position: f64,
+    rand_gen: Xoshiro256PlusPlus,
+    seed: Option<[u8; 32]>,
+}
+
+impl ArpeggiatorEventIter {
+    /// Create a new arpeggiator for the given event iter, and an optional seed for the random
+    /// number generator of the random mode.
+    pub fn new(event_iter: Box<dyn EventIter>, seed: Option<[u8; 32]>) -> Self {
+        let mode = ArpeggiatorMode::default();
+        let octaves = 1;
+        let rate = 1.0;
+        let notes = Vec::new();
+        let note_index = 0;
+        let position = 0.0;
+        let rand_seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let rand_gen = Xoshiro256PlusPlus::from_seed(rand_seed);
+        Self {
+            event_iter,
+            mode,
+            octaves,
+            rate,
+            notes,
+            note_index,
+            position,
+            rand_gen,
+            seed,
+        }
+    }
+
+    /// Return a new arpeggiator which plays notes in the given mode.
+    #[must_use]
+    pub fn with_mode(self, mode: ArpeggiatorMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// Maximum number of octaves. Larger octave ranges get clamped.
+    pub const MAX_OCTAVES: usize = 10;
+    /// Maximum rate in notes per pulse. Larger rates get clamped.
+    pub const MAX_RATE: f32 = 64.0;
+
+    /// Return a new arpeggiator which repeats the held notes in the given number of octaves.
+    /// The number of octaves gets clamped to [`Self::MAX_OCTAVES`].
+    ///
+    /// ### Panics
+    /// Panics when the given number of octaves is 0.
+    #[must_use]
+    pub fn with_octaves(self, octaves: usize) -> Self {
+        assert!(octaves > 0, "Invalid octave range: octaves must be > 0");
+        let octaves = octaves.min(Self::MAX_OCTAVES);
+        Self { octaves, ..self }
+    }
+
+    /// Return a new arpeggiator which plays the given number of notes per pulse.
+    /// The rate gets clamped to [`Self::MAX_RATE`].
+    ///
+    /// ### Panics
+    /// Panics when the given rate is not finite or not > 0.
+    #[must_use]
+    pub fn with_rate(self, rate: f32) -> Self {
+        assert!(
+            rate.is_finite() && rate > 0.0,
+            "Invalid rate: rate must be a finite number > 0"
+        );
+        let rate = rate.min(Self::MAX_RATE);
+        Self { rate, ..self }
+    }
+
+    /// Get current arpeggiator mode.
+    pub fn mode(&self) -> ArpeggiatorMode {
+        self.mode
+    }
+    /// Get current octave range.
+    pub fn octaves(&self) -> usize {
+        self.octaves
+    }
+    /// Get current rate in notes per pulse.
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    // order the given notes in the given mode and repeat them in the given octaves
+    fn arpeggiate(notes: &[NoteEvent], mode: ArpeggiatorMode, octaves: usize) -> Vec<NoteEvent> {
+        let mut sorted = notes.to_vec();
+        if !matches!(mode, ArpeggiatorMode::AsPlayed) {
+            sorted.sort_by_key(|note_event| note_event.note as u8);
+        }
+        let mut arpeggio = Vec::with_capacity(sorted.len() * octaves);
+        for octave in 0..octaves {
+            for note_event in &sorted {
+                let note = note_event.note.transposed(12 * octave as i32);
+                // skip notes which got clamped to the highest note
+                if octave == 0 || note != note_event.note.transposed(12 * (octave as i32 - 1)) {
+                    arpeggio.push(NoteEvent {
+                        note,
+                        ..note_event.clone()
+                    });
+                }
+            }
+        }
+        match mode {
+            ArpeggiatorMode::Down => arpeggio.reverse(),
+            ArpeggiatorMode::UpDown if arpeggio.len() > 2 => {
+                let down = arpeggio[1..arpeggio.len() - 1].iter().rev().cloned();
+                arpeggio = arpeggio.iter().cloned().chain(down).collect();
+            }
+            _ => (),
+        }
+        arpeggio
+    }
+
+    // update held notes from the given event
+    fn hold_notes(&mut self, note_events: &[Option<NoteEvent>]) {
+        let notes = note_events
+            .iter()
+            .flatten()
+            .filter(|note_event| note_event.note.is_note_on())
+            .cloned()
+            .collect::<Vec<_>>();
+        if !notes.is_empty() {
+            self.notes = Self::arpeggiate(&notes, self.mode, self.octaves);
+            if self.note_index >= self.notes.len() {
+                self.note_index = 0;
+            }
+        } else if note_events
+            .iter()
+            .flatten()
+            .any(|note_event| note_event.note.is_note_off())
+        {
+            self.notes.clear();
+            self.note_index = 0;
+        }
+    }
+
+    // get the next note of the arpeggio
+    fn next_note(&mut self) -> NoteEvent {
+        debug_assert!(!self.notes.is_empty(), "expecting some held notes");
+        if matches!(self.mode, ArpeggiatorMode::Random) {
+            let index = self.rand_gen.gen_range(0..self.notes.len());
+            self.notes[index].clone()
+        } else {
+            let note = self.notes[self.note_index].clone();
+            self.note_index = (self.note_index + 1) % self.notes.len();
+            note
+        }
+    }
+}
+
+impl EventIter for ArpeggiatorEventIter {
+    fn set_time_base(&mut self, time_base: &BeatTimeBase) {
+        self.event_iter.set_time_base(time_base);
+    }
+
+    fn set_external_context(&mut self, data: &[(Cow<str>, f64)]) {
+        self.event_iter.set_external_context(data);
+    }
+
+    fn run(
+        &mut self,
+        pulse: PulseIterItem,
+        pulse_pattern_length: usize,
+        emit_event: bool,
+    ) -> Option<Event> {
+        let event = self.event_iter.run(pulse, pulse_pattern_length, emit_event);
+        if !emit_event {
+            return event;
+        }
+        // update held notes or pass other events
+        let stopped = match &event {
+            Some(Event::NoteEvents(note_events)) => {
+                self.hold_notes(note_events);
+                self.notes.is_empty()
+            }
+            Some(_) => return event,
+            None => false,
+        };
+        // play all notes which start within this pulse
+        let rate = self.rate as f64;
+        let start = self.position;
+        let end = start + rate;
+        self.position = end - end.floor();
+        if self.notes.is_empty() {
+            return if stopped { event } else { None };
+        }
+        let mut note_events = vec![];
+        let mut note_start = start.ceil();
+        while note_start < end {
+            let mut note_event = self.next_note();
+            note_event.delay = ((note_start - start) / rate) as f32;
+            if note_event.length.is_none() && rate > 1.0 {
+                note_event.length = Some(1.0 / self.rate);
+            }
+            note_events.push(Some(note_event));
+            note_start += 1.0;
+        }
+        if note_events.is_empty() {
+            None
+        } else {
+            Some(Event::NoteEvents(note_events))
+        }
+    }
+
+    fn duplicate(&self) -> Box<dyn EventIter> {
+        Box::new(Self {
+            event_iter: self.event_iter.duplicate(),
+            notes: self.notes.clone(),
+            rand_gen: self.rand_gen.clone(),
+            ..*self
+        })
+    }
+
+    fn reset(&mut self) {
+        self.event_iter.reset();
+        self.notes.clear();
+        self.note_index = 0;
+        self.position = 0.0;
+        // reset random number generator to its initial state when the arpeggiator is seeded
+        if let Some(seed) = self.seed {
+            self.rand_gen = Xoshiro256PlusPlus::from_seed(seed);
+        }
+        // else create a new random number generator from a random seed
+        else {
+            self.rand_gen = Xoshiro256PlusPlus::from_seed(thread_rng().gen());
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::{fixed::ToFixedEventIter, test_helpers::run_events},
+        Note,
+    };
+
+    fn run_notes(arp: &mut ArpeggiatorEventIter, count: usize) -> Vec<Vec<(Note, f32)>> {
+        run_events(arp, count)
+            .into_iter()
+            .map(|event| match event {
+                Some(Event::NoteEvents(note_events)) => note_events
+                    .into_iter()
+                    .flatten()
+                    .map(|note_event| (note_event.note, note_event.delay))
+                    .collect(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    fn chord() -> Box<dyn EventIter> {
+        let notes = vec![Note::E4, Note::C4, Note::G4];
+        Box::new(
+            notes
+                .into_iter()
+                .map(|note| Some(NoteEvent::from(note)))
+                .collect::<Vec<_>>()
+                .to_event(),
+        )
+    }
+
+    #[test]
+    fn modes() {
+        let notes = |arp: ArpeggiatorEventIter, count| {
+            let mut arp = arp;
+            run_notes(&mut arp, count)
+                .into_iter()
+                .flatten()
+                .map(|(note, _)| note)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            notes(ArpeggiatorEventIter::new(chord(), None), 4),
+            vec![Note::C4, Note::E4, Note::G4, Note::C4]
+        );
+        assert_eq!(
+            notes(
+                ArpeggiatorEventIter::new(chord(), None).with_mode(ArpeggiatorMode::Down),
+                4
+            ),
+            vec![Note::G4, Note::E4, Note::C4, Note::G4]
+        );
+        assert_eq!(
+            notes(
+                ArpeggiatorEventIter::new(chord(), None).with_mode(ArpeggiatorMode::AsPlayed),
+                4
+            ),
+            vec![Note::E4, Note::C4, Note::G4, Note::E4]
+        );
+        assert_eq!(
+            notes(
+                ArpeggiatorEventIter::new(chord(), None)
+                    .with_mode(ArpeggiatorMode::UpDown)
+                    .with_octaves(2),
+                11
+            ),
+            vec![
+                Note::C4,
+                Note::E4,
+                Note::G4,
+                Note::C5,
+                Note::E5,
+                Note::G5,
+                Note::E5,
+                Note::C5,
+                Note::G4,
+                Note::E4,
+                Note::C4
+            ]
+        );
+        let seed = Some([1; 32]);
+        let random = notes(
+            ArpeggiatorEventIter::new(chord(), seed).with_mode(ArpeggiatorMode::Random),
+            16,
+        );
+        assert!(random
+            .iter()
+            .all(|note| [Note::C4, Note::E4, Note::G4].contains(note)));
+        assert_eq!(
+            random,
+            notes(
+                ArpeggiatorEventIter::new(chord(), seed).with_mode(ArpeggiatorMode::Random),
+                16
+            )
+        );
+    }
+
+    #[test]
+    fn rates() {
+        let mut arp = ArpeggiatorEventIter::new(chord(), None).with_rate(2.0);
+        assert_eq!(
+            run_notes(&mut arp, 2),
+            vec![
+                vec![(Note::C4, 0.0), (Note::E4, 0.5)],
+                vec![(Note::G4, 0.0), (Note::C4, 0.5)]
+            ]
+        );
+        let mut arp = ArpeggiatorEventIter::new(chord(), None).with_rate(0.5);
+        assert_eq!(
+            run_notes(&mut arp, 4),
+            vec![vec![(Note::C4, 0.0)], vec![], vec![(Note::E4, 0.0)], vec![]]
+        );
+        let mut arp = ArpeggiatorEventIter::new(chord(), None).with_rate(1.5);
+        assert_eq!(
+            run_notes(&mut arp, 2),
+            vec![
+                vec![(Note::C4, 0.0), (Note::E4, 2.0 / 3.0)],
+                vec![(Note::G4, 1.0 / 3.0)]
+            ]
+        );
+        arp.reset();
+        assert_eq!(
+            run_notes(&mut arp, 1),
+            vec![vec![(Note::C4, 0.0), (Note::E4, 2.0 / 3.0)]]
+        );
+        // rates and octaves get clamped
+        let mut arp = ArpeggiatorEventIter::new(chord(), None)
+            .with_rate(1e30)
+            .with_octaves(1_000_000_000_000);
+        assert_eq!(arp.rate(), ArpeggiatorEventIter::MAX_RATE);
+        assert_eq!(arp.octaves(), ArpeggiatorEventIter::MAX_OCTAVES);
+        assert_eq!(
+            run_notes(&mut arp, 1)[0].len(),
+            ArpeggiatorEventIter::MAX_RATE as usize
+        );
+    }
+}
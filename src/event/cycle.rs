@@ -0,0 +1,791 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt::Debug, rc::Rc};
+
+use fraction::Fraction;
+
+use crate::{
+    event::{Event, EventIter, InstrumentId, NoteEvent},
+    rhythm::tidal::{
+        Chord as CycleChord, Cycle, Event as CycleEvent, Events as CycleEvents,
+        Target as CycleTarget, Value as CycleValue,
+    },
+    BeatTimeBase, Chord, Note, PulseIterItem,
+};
+
+/// Error of a failed mini-notation parse, with the location of the error in the parsed string.
+pub use crate::rhythm::tidal::ParseError as CycleParseError;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Pointer to a function which resolves mini-notation names, such as `bd` in `"bd:3 sn"`, to
+/// instrument ids. The second argument is the name's variant index, the target value of the name
+/// (e.g. `3` in `"bd:3"`), which is 0 when no target is specified.
+pub type CycleNameResolverFn = dyn Fn(&str, usize) -> Option<InstrumentId> + 'static;
+
+/// Pointer to a function which applies mini-notation target values, such as `v0.5` in
+/// `"c4:v0.5"`, to the note events of the target's step. Note event delays are relative to the
+/// step's length here, so a delay of `0.5` delays the note by half of the step's length.
+pub type CycleTargetMapFn = dyn Fn(&str, &mut NoteEvent) + 'static;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Emits [`Event`]s from a [Tidal](https://tidalcycles.org/docs/reference/mini_notation/)
+/// mini-notation cycle.
+///
+/// Each emitted event plays back one entire cycle: all notes of the cycle are packed into a
+/// single polyphonic note event, and the note's start times within the cycle are applied as
+/// note event delays, the note's span lengths as note event lengths. Pitch and integer values
+/// are converted to notes, chords such as `c4'maj` to multiple notes, rests are left out and
+/// holds extend the preceding note. Names are resolved to instruments via an optional name
+/// resolver, see [`Self::with_name_resolver`]. Other value types are ignored.
+///
+/// Target values set note properties, using the same prefixes as note strings in Lua: `#`
+/// (instrument), `v` (volume), `p` (panning) or `d` (delay), e.g. `"c4:v0.5 e4:p-1 g4:#3"`.
+/// See [`Self::with_target_mapping`] to customize this behavior.
+#[derive(Clone)]
+pub struct CycleEventIter {
+    cycle: Cycle,
+    name_resolver: Option<Rc<CycleNameResolverFn>>,
+    target_mapping: Rc<CycleTargetMapFn>,
+}
+
+impl CycleEventIter {
+    /// Create a new cycle event iter from the given mini-notation string, using a random seed
+    /// for random operators in the cycle.
+    ///
+    /// ### Errors
+    /// Returns a [`CycleParseError`] when the given string is not a valid mini-notation.
+    pub fn from_mini(input: &str) -> Result<Self, CycleParseError> {
+        Self::from_mini_with_seed(input, None)
+    }
+
+    /// Create a new cycle event iter from the given mini-notation string and an optional seed
+    /// for random operators in the cycle.
+    ///
+    /// ### Errors
+    /// Returns a [`CycleParseError`] when the given string is not a valid mini-notation.
+    pub fn from_mini_with_seed(
+        input: &str,
+        seed: Option<[u8; 32]>,
+    ) -> Result<Self, CycleParseError> {
+        let cycle = Cycle::from(input, seed)?;
+        let name_resolver = None;
+        let target_mapping = Rc::new(Self::apply_note_property);
+        Ok(Self {
+            cycle,
+            name_resolver,
+            target_mapping,
+        })
+    }
+
+    /// Return a new cycle event iter which resolves names in the cycle to instruments with the
+    /// given function. Resolved names trigger a C5 note with the resolved instrument. Names which
+    /// can not be resolved are ignored.
+    ///
+    /// ### Example
+    /// ```rust,ignore
+    /// let event_iter = CycleEventIter::from_mini("bd*2 [~ sn] hh*4")?
+    ///     .with_name_resolver(move |name, variant| sample_pool.named_sample_id(name, variant));
+    /// ```
+    #[must_use]
+    pub fn with_name_resolver<F>(self, resolver: F) -> Self
+    where
+        F: Fn(&str, usize) -> Option<InstrumentId> + 'static,
+    {
+        Self {
+            name_resolver: Some(Rc::new(resolver)),
+            ..self
+        }
+    }
+
+    /// Return a new cycle event iter which applies target values of the cycle with the given
+    /// function instead of the default note property mapping. Target values are passed as
+    /// strings, so `"c4:3 e4:v0.5"` passes `3` and `v0.5` to the mapping function.
+    #[must_use]
+    pub fn with_target_mapping<F>(self, mapping: F) -> Self
+    where
+        F: Fn(&str, &mut NoteEvent) + 'static,
+    {
+        Self {
+            target_mapping: Rc::new(mapping),
+            ..self
+        }
+    }
+
+    /// Maximum factor of [`Self::fast`] and [`Self::slow`]. Factors get clamped to the range
+    /// \[1 / `MAX_SPEED_FACTOR` - `MAX_SPEED_FACTOR`\].
+    pub const MAX_SPEED_FACTOR: f64 = 1024.0;
+
+    /// Return a new cycle event iter which plays the cycle faster by the given factor, e.g.
+    /// a factor of 2 plays two cycles within one cycle. The factor gets clamped, see
+    /// [`Self::MAX_SPEED_FACTOR`].
+    ///
+    /// ### Panics
+    /// Panics when the given factor is not finite or not > 0.
+    #[must_use]
+    pub fn fast(self, factor: f64) -> Self {
+        let factor = Self::speed_factor(factor);
+        self.with_cycle(|cycle| cycle.fast(Fraction::from(factor)))
+    }
+
+    /// Return a new cycle event iter which plays the cycle slower by the given factor, e.g.
+    /// a factor of 2 spreads a single cycle over two cycles. The factor gets clamped, see
+    /// [`Self::MAX_SPEED_FACTOR`].
+    ///
+    /// ### Panics
+    /// Panics when the given factor is not finite or not > 0.
+    #[must_use]
+    pub fn slow(self, factor: f64) -> Self {
+        let factor = Self::speed_factor(factor);
+        self.with_cycle(|cycle| cycle.slow(Fraction::from(factor)))
+    }
+
+    /// Return a new cycle event iter which reverses the events within each cycle.
+    #[must_use]
+    pub fn rev(self) -> Self {
+        self.with_cycle(|cycle| cycle.rev())
+    }
+
+    /// Return a new cycle event iter which applies the given transformation every `n`th
+    /// cycle, starting with the first cycle.
+    ///
+    /// ### Panics
+    /// Panics when `n` is 0.
+    #[must_use]
+    pub fn every<F>(self, n: usize, fun: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        let transformed = fun(self.clone()).cycle;
+        self.with_cycle(|cycle| cycle.every(n, transformed))
+    }
+
+    /// Return a new cycle event iter which stacks the cycle with a transformed copy of itself,
+    /// shifted in time by the given fraction of a cycle.
+    #[must_use]
+    pub fn off<F>(self, time: f64, fun: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        let this = self.clone();
+        self.with_cycle(|cycle| {
+            cycle.off(Fraction::from(time), |shifted| {
+                fun(Self {
+                    cycle: shifted,
+                    ..this
+                })
+                .cycle
+            })
+        })
+    }
+
+    /// Return a new cycle event iter which randomly removes events with the given probability
+    /// in range \[0 - 1\].
+    #[must_use]
+    pub fn degrade_by(self, amount: f64) -> Self {
+        self.with_cycle(|cycle| cycle.degrade_by(amount))
+    }
+
+    /// Return a new cycle event iter which plays the cycle forwards, then backwards.
+    #[must_use]
+    pub fn palindrome(self) -> Self {
+        self.with_cycle(|cycle| cycle.palindrome())
+    }
+
+    /// Return a new cycle event iter which divides the cycle into `n` parts, and shifts the
+    /// cycle's start by one part in each cycle.
+    ///
+    /// ### Panics
+    /// Panics when `n` is 0.
+    #[must_use]
+    pub fn iter(self, n: usize) -> Self {
+        self.with_cycle(|cycle| cycle.iter(n))
+    }
+
+    /// Return a new cycle event iter which divides the cycle into `n` parts, and applies the
+    /// given transformation to one part in each cycle, in turn.
+    ///
+    /// ### Panics
+    /// Panics when `n` is 0.
+    #[must_use]
+    pub fn chunk<F>(self, n: usize, fun: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        let transformed = fun(self.clone()).cycle;
+        self.with_cycle(|cycle| cycle.chunk(n, transformed))
+    }
+
+    /// Maximum number of cycles a [`Self::query`] window may span. Larger windows get truncated.
+    pub const MAX_QUERY_CYCLES: u64 = 1024;
+
+    /// Note events of all cycle events which intersect the given window in cycle time, e.g. 1/2
+    /// to 1 is the second half of the first cycle and 3 to 4 the entire fourth cycle.
+    ///
+    /// Returns the start and end of each note in cycle time, followed by the note event. The
+    /// note's delay and length are applied to the returned start and end, so the note events have
+    /// no delay and length. Unlike [`EventIter::run`], this does not advance the iter's state,
+    /// so any window can be queried at any time. Windows which span more than
+    /// [`Self::MAX_QUERY_CYCLES`] cycles get truncated.
+    pub fn query(&self, start: Fraction, end: Fraction) -> Vec<(Fraction, Fraction, NoteEvent)> {
+        let end = end.min(start + Fraction::from(Self::MAX_QUERY_CYCLES));
+        let channels = CycleEvents::merge(&self.cycle.query(start, end));
+        channels
+            .iter()
+            .flatten()
+            .flat_map(|event| {
+                let span_start = event.span().start();
+                let span_length = event.span().length();
+                self.step_note_events(event)
+                    .into_iter()
+                    .map(move |mut note_event| {
+                        let note_start =
+                            span_start + Fraction::from(note_event.delay) * span_length;
+                        let note_length = Fraction::from(note_event.length.unwrap_or(1.0));
+                        note_event.delay = 0.0;
+                        note_event.length = None;
+                        (
+                            note_start,
+                            note_start + note_length * span_length,
+                            note_event,
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Normalized mini-notation string of the parsed cycle, e.g. `"a4 [b4 c4]"` for an input of
+    /// `"a  [B4 c]"`. Parsing the returned string results in a cycle with the same output.
+    ///
+    /// Transformations such as [`Self::fast`] or [`Self::every`] are not part of the
+    /// mini-notation and thus are not printed: the returned string is the untransformed cycle.
+    pub fn to_mini_notation(&self) -> String {
+        self.cycle.to_mini_notation()
+    }
+
+    // Validate and clamp a fast or slow factor.
+    fn speed_factor(factor: f64) -> f64 {
+        assert!(
+            factor.is_finite() && factor > 0.0,
+            "Invalid speed factor: factor must be a finite number > 0"
+        );
+        factor.clamp(1.0 / Self::MAX_SPEED_FACTOR, Self::MAX_SPEED_FACTOR)
+    }
+
+    // Apply the given transformation on the cycle.
+    fn with_cycle<F>(self, fun: F) -> Self
+    where
+        F: FnOnce(Cycle) -> Cycle,
+    {
+        Self {
+            cycle: fun(self.cycle),
+            ..self
+        }
+    }
+
+    // Convert a cycle chord to a list of notes, applying the chord's inversion and voicing.
+    fn chord_notes(chord: &CycleChord) -> Vec<Note> {
+        let root = Note::from(chord.pitch().midi_note());
+        let mut intervals: Vec<usize> = match Chord::try_from((root, chord.mode())) {
+            Ok(chord) => chord.intervals().iter().map(|i| *i as usize).collect(),
+            Err(_) => return vec![],
+        };
+        if let Some(voicing) = chord.voicing() {
+            // expand the chord with octaves of its intervals or shrink it
+            let len = intervals.len();
+            intervals = (0..voicing as usize)
+                .map(|index| intervals[index % len] + 12 * (index / len))
+                .collect();
+        }
+        for _ in 0..chord.inversion() as usize % intervals.len().max(1) {
+            // move the lowest note an octave up
+            let lowest = intervals.remove(0);
+            intervals.push(lowest + 12);
+        }
+        intervals
+            .iter()
+            .map(|interval| Note::from((u8::from(root) as usize + interval).min(0x7f) as u8))
+            .collect()
+    }
+
+    // Default target mapping: apply prefixed note property values to the note event.
+    // Values are clamped to their valid ranges, unknown properties are ignored.
+    fn apply_note_property(target: &str, note_event: &mut NoteEvent) {
+        let mut chars = target.chars();
+        let prefix = chars.next();
+        let value = chars.as_str();
+        match prefix {
+            Some('#') => {
+                if let Ok(instrument) = value.parse::<usize>() {
+                    note_event.instrument = Some(InstrumentId::from(instrument));
+                }
+            }
+            Some('v') => {
+                if let Ok(volume) = value.parse::<f32>() {
+                    note_event.volume = volume.clamp(0.0, 1.0);
+                }
+            }
+            Some('p') => {
+                if let Ok(panning) = value.parse::<f32>() {
+                    note_event.panning = panning.clamp(-1.0, 1.0);
+                }
+            }
+            Some('d') => {
+                if let Ok(delay) = value.parse::<f32>() {
+                    note_event.delay = delay.clamp(0.0, 1.0);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Convert a single cycle event to note events, with delays and lengths relative to the
+    // event's span.
+    fn step_note_events(&self, event: &CycleEvent) -> Vec<NoteEvent> {
+        let mut instrument = None;
+        let notes = match event.value() {
+            CycleValue::Pitch(pitch) => vec![Note::from(pitch.midi_note())],
+            CycleValue::Chord(chord) => Self::chord_notes(chord),
+            CycleValue::Integer(value) => vec![Note::from((*value).clamp(0, 0x7f) as u8)],
+            CycleValue::Name(name) => {
+                let variant = match event.target_value() {
+                    CycleTarget::Index(index) => (*index).max(0) as usize,
+                    CycleTarget::None | CycleTarget::Name(_) => 0,
+                };
+                instrument = self
+                    .name_resolver
+                    .as_ref()
+                    .and_then(|resolver| resolver(name, variant));
+                if instrument.is_none() {
+                    return vec![];
+                }
+                vec![Note::C5]
+            }
+            CycleValue::Rest | CycleValue::Hold | CycleValue::Float(_) => vec![],
+        };
+        let target = match event.target_value() {
+            CycleTarget::None => None,
+            CycleTarget::Index(index) => Some(index.to_string()),
+            CycleTarget::Name(name) => Some(name.clone()),
+        };
+        notes
+            .into_iter()
+            .map(|note| {
+                // notes play until the end of their span, including merged holds
+                let length = Some(1.0);
+                let mut note_event = NoteEvent {
+                    note,
+                    instrument,
+                    volume: 1.0,
+                    panning: 0.0,
+                    delay: 0.0,
+                    length,
+                    glide: None,
+                    parameters: BTreeMap::new(),
+                };
+                if let Some(target) = &target {
+                    (self.target_mapping)(target, &mut note_event);
+                }
+                note_event
+            })
+            .collect()
+    }
+
+    // Convert a single cycle event to note events, with delays and lengths relative to the
+    // cycle.
+    fn note_events(&self, event: &CycleEvent) -> Vec<NoteEvent> {
+        let start = f32::try_from(event.span().start()).unwrap_or(0.0);
+        let span_length = f32::try_from(event.span().length()).unwrap_or(0.0);
+        self.step_note_events(event)
+            .into_iter()
+            .map(|mut note_event| {
+                note_event.delay = start + note_event.delay * span_length;
+                note_event.length = note_event.length.map(|length| length * span_length);
+                note_event
+            })
+            .collect()
+    }
+
+    // Generate the next cycle and convert all its channels into a single note event vector.
+    fn next_note_events(&mut self) -> Vec<Option<NoteEvent>> {
+        let channels = CycleEvents::merge(&self.cycle.generate());
+        channels
+            .iter()
+            .flatten()
+            .flat_map(|event| self.note_events(event))
+            .map(Some)
+            .collect()
+    }
+}
+
+impl Debug for CycleEventIter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CycleEventIter")
+            .field("cycle", &self.cycle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EventIter for CycleEventIter {
+    fn set_time_base(&mut self, _time_base: &BeatTimeBase) {
+        // nothing to do
+    }
+
+    fn set_external_context(&mut self, _data: &[(Cow<str>, f64)]) {
+        // nothing to do
+    }
+
+    fn run(
+        &mut self,
+        _pulse: PulseIterItem,
+        _pulse_pattern_length: usize,
+        emit_event: bool,
+    ) -> Option<Event> {
+        if !emit_event {
+            return None;
+        }
+        let note_events = self.next_note_events();
+        if note_events.is_empty() {
+            None
+        } else {
+            Some(Event::NoteEvents(note_events))
+        }
+    }
+
+    fn duplicate(&self) -> Box<dyn EventIter> {
+        Box::new(self.clone())
+    }
+
+    fn reset(&mut self) {
+        self.cycle.reset();
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn note_event(note: Note, delay: f32, length: f32) -> Option<NoteEvent> {
+        Some(NoteEvent {
+            note,
+            instrument: None,
+            volume: 1.0,
+            panning: 0.0,
+            delay,
+            length: Some(length),
+            glide: None,
+            parameters: BTreeMap::new(),
+        })
+    }
+
+    #[test]
+    fn cycle_events() -> Result<(), String> {
+        let mut event_iter = CycleEventIter::from_mini("c4 [e4 g4] <a4 b4> ~")?;
+        let pulse = PulseIterItem::default();
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.25),
+                note_event(Note::E4, 0.25, 0.125),
+                note_event(Note::G4, 0.375, 0.125),
+                note_event(Note::A4, 0.5, 0.25),
+            ]))
+        );
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.25),
+                note_event(Note::E4, 0.25, 0.125),
+                note_event(Note::G4, 0.375, 0.125),
+                note_event(Note::B4, 0.5, 0.25),
+            ]))
+        );
+        assert_eq!(event_iter.run(pulse, 1, false), None);
+
+        event_iter.reset();
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.25),
+                note_event(Note::E4, 0.25, 0.125),
+                note_event(Note::G4, 0.375, 0.125),
+                note_event(Note::A4, 0.5, 0.25),
+            ]))
+        );
+
+        let mut event_iter = CycleEventIter::from_mini("48 _ ~ ~, 60")?;
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.5),
+                note_event(Note::C5, 0.0, 1.0),
+            ]))
+        );
+
+        // holds extend the length of notes
+        let mut event_iter = CycleEventIter::from_mini("c4 _ _ e4")?;
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.75),
+                note_event(Note::E4, 0.75, 0.25),
+            ]))
+        );
+
+        // queries return events of arbitrary windows without advancing the cycle
+        let event_iter = CycleEventIter::from_mini("c4 <e4 g4:d0.5>")?;
+        let note_event = |note: Note| NoteEvent {
+            length: None,
+            ..note_event(note, 0.0, 0.0).unwrap()
+        };
+        assert_eq!(
+            event_iter.query(Fraction::new(3u8, 2u8), Fraction::new(9u8, 4u8)),
+            vec![
+                (
+                    Fraction::new(7u8, 4u8),
+                    Fraction::new(9u8, 4u8),
+                    note_event(Note::G4)
+                ),
+                (
+                    Fraction::from(2),
+                    Fraction::new(5u8, 2u8),
+                    note_event(Note::C4)
+                ),
+            ]
+        );
+        assert_eq!(
+            event_iter.query(Fraction::new(3u8, 4u8), Fraction::from(1)),
+            vec![(
+                Fraction::new(1u8, 2u8),
+                Fraction::from(1),
+                note_event(Note::E4)
+            )]
+        );
+        assert!(event_iter
+            .query(Fraction::from(1), Fraction::from(1))
+            .is_empty());
+        assert_eq!(
+            event_iter
+                .query(Fraction::from(0), Fraction::from(u64::MAX))
+                .len(),
+            2 * CycleEventIter::MAX_QUERY_CYCLES as usize
+        );
+
+        assert!(CycleEventIter::from_mini("c4 [e4").is_err());
+        assert_eq!(
+            CycleEventIter::from_mini("a  [B4 c]")?.to_mini_notation(),
+            "a4 [b4 c4]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cycle_chords() -> Result<(), String> {
+        let pulse = PulseIterItem::default();
+        let mut event_iter = CycleEventIter::from_mini("c4'maj e4'min7'ii g'5")?;
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 1.0 / 3.0),
+                note_event(Note::E4, 0.0, 1.0 / 3.0),
+                note_event(Note::G4, 0.0, 1.0 / 3.0),
+                note_event(Note::B4, 1.0 / 3.0, 1.0 / 3.0),
+                note_event(Note::D5, 1.0 / 3.0, 1.0 / 3.0),
+                note_event(Note::E5, 1.0 / 3.0, 1.0 / 3.0),
+                note_event(Note::G5, 1.0 / 3.0, 1.0 / 3.0),
+                note_event(Note::G4, 2.0 / 3.0, 1.0 / 3.0),
+                note_event(Note::D5, 2.0 / 3.0, 1.0 / 3.0),
+            ]))
+        );
+        let mut event_iter = CycleEventIter::from_mini("c'maj'5 <c'maj c'maj'i>")?;
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.5),
+                note_event(Note::E4, 0.0, 0.5),
+                note_event(Note::G4, 0.0, 0.5),
+                note_event(Note::C5, 0.0, 0.5),
+                note_event(Note::E5, 0.0, 0.5),
+                note_event(Note::C4, 0.5, 0.5),
+                note_event(Note::E4, 0.5, 0.5),
+                note_event(Note::G4, 0.5, 0.5),
+            ]))
+        );
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.5),
+                note_event(Note::E4, 0.0, 0.5),
+                note_event(Note::G4, 0.0, 0.5),
+                note_event(Note::C5, 0.0, 0.5),
+                note_event(Note::E5, 0.0, 0.5),
+                note_event(Note::E4, 0.5, 0.5),
+                note_event(Note::G4, 0.5, 0.5),
+                note_event(Note::C5, 0.5, 0.5),
+            ]))
+        );
+
+        assert!(CycleEventIter::from_mini("c4'qwe").is_err());
+        assert!(CycleEventIter::from_mini("c4'").is_err());
+        assert!(CycleEventIter::from_mini("c4'maj'x").is_err());
+        assert!(CycleEventIter::from_mini("c4'maj'129").is_err());
+
+        // large voicings get clamped to the valid note range
+        let mut event_iter = CycleEventIter::from_mini("c'maj'99")?;
+        match event_iter.run(pulse, 1, true) {
+            Some(Event::NoteEvents(notes)) => {
+                assert_eq!(notes.len(), 99);
+                assert_eq!(notes.last(), Some(&note_event(Note::G10, 0.0, 1.0)));
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cycle_transforms() -> Result<(), String> {
+        let pulse = PulseIterItem::default();
+        let mut event_iter = CycleEventIter::from_mini("c4 e4")?
+            .every(2, |c| c.fast(2.0))
+            .off(0.25, |c| c.rev());
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.25),
+                note_event(Note::E4, 0.25, 0.25),
+                note_event(Note::C4, 0.5, 0.25),
+                note_event(Note::E4, 0.75, 0.25),
+                note_event(Note::C4, 0.0, 0.25),
+                note_event(Note::E4, 0.25, 0.25),
+                note_event(Note::C4, 0.5, 0.25),
+            ]))
+        );
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(Note::C4, 0.0, 0.5),
+                note_event(Note::E4, 0.5, 0.5),
+                note_event(Note::C4, 0.25, 0.5),
+                note_event(Note::E4, 0.75, 0.25),
+            ]))
+        );
+
+        // speed factors get clamped
+        let mut event_iter = CycleEventIter::from_mini("c4")?.fast(1e9);
+        match event_iter.run(pulse, 1, true) {
+            Some(Event::NoteEvents(notes)) => {
+                assert_eq!(notes.len(), CycleEventIter::MAX_SPEED_FACTOR as usize)
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cycle_targets() -> Result<(), String> {
+        let pulse = PulseIterItem::default();
+        let mut event_iter = CycleEventIter::from_mini("c4:v0.5 e4:p-1 g4:#3 a4:d0.5")?;
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                Some(NoteEvent {
+                    note: Note::C4,
+                    instrument: None,
+                    volume: 0.5,
+                    panning: 0.0,
+                    delay: 0.0,
+                    length: Some(0.25),
+                    glide: None,
+                    parameters: BTreeMap::new(),
+                }),
+                Some(NoteEvent {
+                    note: Note::E4,
+                    instrument: None,
+                    volume: 1.0,
+                    panning: -1.0,
+                    delay: 0.25,
+                    length: Some(0.25),
+                    glide: None,
+                    parameters: BTreeMap::new(),
+                }),
+                Some(NoteEvent {
+                    note: Note::G4,
+                    instrument: Some(InstrumentId::from(3)),
+                    volume: 1.0,
+                    panning: 0.0,
+                    delay: 0.5,
+                    length: Some(0.25),
+                    glide: None,
+                    parameters: BTreeMap::new(),
+                }),
+                note_event(Note::A4, 0.875, 0.25),
+            ]))
+        );
+
+        let mut event_iter =
+            CycleEventIter::from_mini("c4:2 e4:v.25")?.with_target_mapping(|target, note_event| {
+                if let Ok(volume) = target.parse::<f32>() {
+                    note_event.volume = volume / 10.0;
+                }
+            });
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                Some(NoteEvent {
+                    note: Note::C4,
+                    instrument: None,
+                    volume: 0.2,
+                    panning: 0.0,
+                    delay: 0.0,
+                    length: Some(0.5),
+                    glide: None,
+                    parameters: BTreeMap::new(),
+                }),
+                note_event(Note::E4, 0.5, 0.5),
+            ]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cycle_names() -> Result<(), String> {
+        let pulse = PulseIterItem::default();
+        let note_event = |instrument: usize, delay: f32, length: f32| {
+            Some(NoteEvent {
+                note: Note::C5,
+                instrument: Some(InstrumentId::from(instrument)),
+                volume: 1.0,
+                panning: 0.0,
+                delay,
+                length: Some(length),
+                glide: None,
+                parameters: BTreeMap::new(),
+            })
+        };
+
+        // without resolver, names are ignored
+        let mut event_iter = CycleEventIter::from_mini("bd sn")?;
+        assert_eq!(event_iter.run(pulse, 1, true), None);
+
+        let mut event_iter =
+            CycleEventIter::from_mini("bd*2 sn bd:3 hh")?.with_name_resolver(|name, variant| {
+                match name {
+                    "bd" => Some(InstrumentId::from(1 + variant)),
+                    "sn" => Some(InstrumentId::from(10)),
+                    _ => None,
+                }
+            });
+        assert_eq!(
+            event_iter.run(pulse, 1, true),
+            Some(Event::NoteEvents(vec![
+                note_event(1, 0.0, 0.125),
+                note_event(1, 0.125, 0.125),
+                note_event(10, 0.25, 0.25),
+                note_event(4, 0.5, 0.25),
+            ]))
+        );
+        assert!(event_iter.duplicate().run(pulse, 1, true).is_some());
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 
 use crate::{
-    event::{Event, EventIter, NoteEvent, ParameterChangeEvent},
+    event::{Event, EventIter, NoteEvent, ParameterChangeEvent, PitchBendEvent, TempoChangeEvent},
     BeatTimeBase, Note, PulseIterItem,
 };
 
@@ -115,6 +115,20 @@ impl ToFixedEventIter for ParameterChangeEvent {
     }
 }
 
+impl ToFixedEventIter for TempoChangeEvent {
+    /// Wrap a [`TempoChangeEvent`] into a new [`FixedEventIter`].
+    fn to_event(self) -> FixedEventIter {
+        FixedEventIter::new(vec![Event::TempoChangeEvent(self)])
+    }
+}
+
+impl ToFixedEventIter for PitchBendEvent {
+    /// Wrap a [`PitchBendEvent`] into a new [`FixedEventIter`].
+    fn to_event(self) -> FixedEventIter {
+        FixedEventIter::new(vec![Event::PitchBendEvent(self)])
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 
 pub trait ToFixedEventIterSequence {
@@ -155,3 +169,15 @@ impl ToFixedEventIterSequence for Vec<ParameterChangeEvent> {
         FixedEventIter::new(sequence)
     }
 }
+
+impl ToFixedEventIterSequence for Vec<TempoChangeEvent> {
+    /// Wrap a vector of [`TempoChangeEvent`] into a new [`FixedEventIter`]
+    /// resulting into a sequence of tempo changes.
+    fn to_event_sequence(self) -> FixedEventIter {
+        let mut sequence = Vec::with_capacity(self.len());
+        for t in self {
+            sequence.push(Event::TempoChangeEvent(t));
+        }
+        FixedEventIter::new(sequence)
+    }
+}
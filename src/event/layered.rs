@@ -0,0 +1,242 @@
+use std::borrow::Cow;
+
+use crate::{
+    event::{Event, EventIter, NoteEvent},
+    BeatTimeBase, PulseIterItem,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Runs multiple [`EventIter`]s in parallel on the same pulses and merges their note events into
+/// a single polyphonic note event.
+///
+/// Each layer gets its own fixed range of voices in the merged event, so notes of different
+/// layers never stop each other in players. Layers which emit no note events fill their voices
+/// with `None`. Notes exceeding a layer's voice count are dropped.
+///
+/// Other events, such as parameter changes, are only emitted when no layer emits note events in
+/// the same pulse: else they are dropped. If multiple layers emit other events, the first layer's
+/// event wins and the others are dropped too.
+#[derive(Debug)]
+pub struct LayeredEventIter {
+    layers: Vec<Box<dyn EventIter>>,
+    voice_counts: Vec<usize>,
+}
+
+impl LayeredEventIter {
+    /// Create a new layered event iter from the given event iters and the number of voices each
+    /// layer uses in the merged note events.
+    ///
+    /// ### Panics
+    /// Panics when the number of voice counts doesn't match the number of layers.
+    pub fn new(layers: Vec<Box<dyn EventIter>>, voice_counts: Vec<usize>) -> Self {
+        assert_eq!(
+            layers.len(),
+            voice_counts.len(),
+            "Invalid voice counts: expecting a voice count for each layer"
+        );
+        Self {
+            layers,
+            voice_counts,
+        }
+    }
+
+    /// Number of layers.
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Voice offsets of all layers in the merged note events.
+    pub fn voice_offsets(&self) -> Vec<usize> {
+        self.voice_counts
+            .iter()
+            .scan(0, |offset, count| {
+                let layer_offset = *offset;
+                *offset += count;
+                Some(layer_offset)
+            })
+            .collect()
+    }
+}
+
+impl EventIter for LayeredEventIter {
+    fn set_time_base(&mut self, time_base: &BeatTimeBase) {
+        for layer in &mut self.layers {
+            layer.set_time_base(time_base);
+        }
+    }
+
+    fn set_external_context(&mut self, data: &[(Cow<str>, f64)]) {
+        for layer in &mut self.layers {
+            layer.set_external_context(data);
+        }
+    }
+
+    fn run(
+        &mut self,
+        pulse: PulseIterItem,
+        pulse_pattern_length: usize,
+        emit_event: bool,
+    ) -> Option<Event> {
+        // run all layers, also in gated pulses, so all layers advance their state
+        let mut layer_notes: Vec<Option<Vec<Option<NoteEvent>>>> =
+            Vec::with_capacity(self.layers.len());
+        let mut other_event = None;
+        for layer in &mut self.layers {
+            match layer.run(pulse, pulse_pattern_length, emit_event) {
+                Some(Event::NoteEvents(notes)) => layer_notes.push(Some(notes)),
+                Some(event) => {
+                    if other_event.is_none() {
+                        other_event = Some(event);
+                    }
+                    layer_notes.push(None);
+                }
+                None => layer_notes.push(None),
+            }
+        }
+        if !emit_event {
+            return None;
+        }
+        if layer_notes.iter().all(Option::is_none) {
+            return other_event;
+        }
+        // merge note events into the voice ranges of each layer
+        let mut merged_notes = Vec::with_capacity(self.voice_counts.iter().sum());
+        for (notes, voice_count) in layer_notes.into_iter().zip(self.voice_counts.iter()) {
+            let mut notes = notes.unwrap_or_default();
+            notes.resize(*voice_count, None);
+            merged_notes.extend(notes);
+        }
+        // trailing empty voices carry no information
+        while merged_notes.last().is_some_and(Option::is_none) {
+            merged_notes.pop();
+        }
+        Some(Event::NoteEvents(merged_notes))
+    }
+
+    fn duplicate(&self) -> Box<dyn EventIter> {
+        Box::new(Self {
+            layers: self.layers.iter().map(|layer| layer.duplicate()).collect(),
+            voice_counts: self.voice_counts.clone(),
+        })
+    }
+
+    fn reset(&mut self) {
+        for layer in &mut self.layers {
+            layer.reset();
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::{
+            fixed::{ToFixedEventIter, ToFixedEventIterSequence},
+            new_note, new_parameter_change,
+            test_helpers::run_events,
+            ParameterId,
+        },
+        Note,
+    };
+
+    #[test]
+    fn layers() {
+        let bass = || vec![new_note("c3"), new_note("g3")].to_event_sequence();
+        let chords = || {
+            vec![
+                vec![new_note("c4"), new_note("e4"), new_note("g4")],
+                vec![],
+                vec![new_note("b3"), new_note("d4")],
+            ]
+            .to_event_sequence()
+        };
+        let mut layered =
+            LayeredEventIter::new(vec![Box::new(bass()), Box::new(chords())], vec![1, 3]);
+        assert_eq!(layered.layer_count(), 2);
+        assert_eq!(layered.voice_offsets(), vec![0, 1]);
+        let note_events = |notes: Vec<Option<Note>>| {
+            Some(Event::NoteEvents(
+                notes
+                    .into_iter()
+                    .map(|note| note.map(NoteEvent::from))
+                    .collect(),
+            ))
+        };
+        assert_eq!(
+            run_events(&mut layered, 4),
+            vec![
+                note_events(vec![
+                    Some(Note::C3),
+                    Some(Note::C4),
+                    Some(Note::E4),
+                    Some(Note::G4)
+                ]),
+                note_events(vec![Some(Note::G3)]),
+                note_events(vec![Some(Note::C3), Some(Note::B3), Some(Note::D4)]),
+                note_events(vec![
+                    Some(Note::G3),
+                    Some(Note::C4),
+                    Some(Note::E4),
+                    Some(Note::G4)
+                ]),
+            ]
+        );
+
+        // voice offsets are fixed and excess notes are dropped
+        let mut layered =
+            LayeredEventIter::new(vec![Box::new(chords()), Box::new(bass())], vec![2, 1]);
+        assert_eq!(
+            run_events(&mut layered, 3),
+            vec![
+                note_events(vec![Some(Note::C4), Some(Note::E4), Some(Note::C3)]),
+                note_events(vec![None, None, Some(Note::G3)]),
+                note_events(vec![Some(Note::B3), Some(Note::D4), Some(Note::C3)]),
+            ]
+        );
+
+        // duplicates and resets
+        let mut duplicate = layered.duplicate();
+        let pulse = PulseIterItem::default();
+        assert_eq!(
+            duplicate.run(pulse, 1, true),
+            note_events(vec![Some(Note::C4), Some(Note::E4), Some(Note::G3)])
+        );
+        layered.reset();
+        assert_eq!(
+            run_events(&mut layered, 1),
+            vec![note_events(vec![
+                Some(Note::C4),
+                Some(Note::E4),
+                Some(Note::C3)
+            ])]
+        );
+
+        // other events are dropped when layers emit notes, and gated pulses emit nothing
+        let parameter = new_parameter_change(ParameterId::from(1), 0.5);
+        let mut layered = LayeredEventIter::new(
+            vec![
+                Box::new(parameter.clone().to_event()),
+                Box::new(new_note("c4").to_event()),
+            ],
+            vec![0, 1],
+        );
+        assert_eq!(
+            run_events(&mut layered, 2),
+            vec![
+                note_events(vec![Some(Note::C4)]),
+                note_events(vec![Some(Note::C4)])
+            ]
+        );
+        assert_eq!(layered.run(pulse, 1, false), None);
+        let mut layered =
+            LayeredEventIter::new(vec![Box::new(parameter.clone().to_event())], vec![0]);
+        assert_eq!(
+            run_events(&mut layered, 1),
+            vec![Some(Event::ParameterChangeEvent(parameter))]
+        );
+    }
+}
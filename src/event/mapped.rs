@@ -0,0 +1,111 @@
+use std::{borrow::Cow, fmt::Debug};
+
+use crate::{
+    event::{Event, EventIter},
+    BeatTimeBase, PulseIterItem,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Pointer to a function which maps an Event.
+type EventMapFn = dyn FnMut(Event) -> Event + 'static;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Endlessly emits [`Event`]s which get mapped with a custom closure in each iter step.
+///
+/// Unlike a [`MutatedEventIter`](super::mutated::MutatedEventIter), the closure gets called with
+/// the original events in each step, so mappings don't accumulate. Use the closure's state to
+/// apply changes which evolve over time. Created and chained via
+/// [`ToMutatedEventIter::mutate`](super::mutated::ToMutatedEventIter::mutate).
+///
+/// NB: This event iter can not be cloned. `clone_dyn` thus will cause a panic!
+pub struct MappedEventIter {
+    events: Vec<Event>,
+    event_index: usize,
+    map: Box<EventMapFn>,
+    reset_map: Box<dyn Fn() -> Box<EventMapFn>>,
+}
+
+impl MappedEventIter {
+    pub fn new<F>(events: Vec<Event>, map: F) -> Self
+    where
+        F: FnMut(Event) -> Event + Clone + 'static,
+    {
+        // capture initial map state
+        let reset_map: Box<dyn Fn() -> Box<EventMapFn>> = Box::new(move || Box::new(map.clone()));
+        Self::with_reset_map(events, reset_map)
+    }
+
+    /// Chain another mapping, which gets applied after all existing mappings. The mapping
+    /// closures get reset to their initial state.
+    #[must_use]
+    pub fn chain<F>(self, map: F) -> Self
+    where
+        F: FnMut(Event) -> Event + Clone + 'static,
+    {
+        let prev_reset_map = self.reset_map;
+        let reset_map: Box<dyn Fn() -> Box<EventMapFn>> = Box::new(move || {
+            let mut prev_map = prev_reset_map();
+            let mut map = map.clone();
+            Box::new(move |event| map(prev_map(event)))
+        });
+        Self::with_reset_map(self.events, reset_map)
+    }
+
+    fn with_reset_map(events: Vec<Event>, reset_map: Box<dyn Fn() -> Box<EventMapFn>>) -> Self {
+        let event_index = 0;
+        let map = reset_map();
+        Self {
+            events,
+            event_index,
+            map,
+            reset_map,
+        }
+    }
+}
+
+impl Debug for MappedEventIter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("MappedEventIter")
+            .field("events", &self.events)
+            .field("event_index", &self.event_index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EventIter for MappedEventIter {
+    fn set_time_base(&mut self, _time_base: &BeatTimeBase) {
+        // nothing to do
+    }
+
+    fn set_external_context(&mut self, _data: &[(Cow<str>, f64)]) {
+        // nothing to do
+    }
+
+    fn run(
+        &mut self,
+        _pulse: PulseIterItem,
+        _pulse_pattern_length: usize,
+        emit_event: bool,
+    ) -> Option<Event> {
+        if !emit_event || self.events.is_empty() {
+            return None;
+        }
+        let event = (self.map)(self.events[self.event_index].clone());
+        self.event_index += 1;
+        if self.event_index >= self.events.len() {
+            self.event_index = 0;
+        }
+        Some(event)
+    }
+
+    fn duplicate(&self) -> Box<dyn EventIter> {
+        panic!("Mapped event iters can't be cloned")
+    }
+
+    fn reset(&mut self) {
+        self.event_index = 0;
+        self.map = (self.reset_map)();
+    }
+}
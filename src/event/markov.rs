@@ -0,0 +1,220 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::{
+    event::{Event, EventIter},
+    BeatTimeBase, PulseIterItem,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Emits new [`Event`]s from a Markov chain, which learned the transition probabilities of a given
+/// sequence of events.
+///
+/// The chain is of the given order: the next event depends on the last `order` emitted events.
+/// Transitions are learned from the given sequence as if it was looped, so the end of the
+/// sequence transitions back to its start. Playback starts with the first `order` events of the
+/// sequence, followed by randomly chosen, learned transitions.
+#[derive(Clone, Debug)]
+pub struct MarkovEventIter {
+    events: Vec<Event>,
+    transitions: BTreeMap<Vec<usize>, Vec<(usize, usize)>>,
+    initial_state: Vec<usize>,
+    order: usize,
+    state: Vec<usize>,
+    state_index: usize,
+    rand_gen: Xoshiro256PlusPlus,
+    seed: Option<[u8; 32]>,
+}
+
+impl MarkovEventIter {
+    /// Maximum order of a Markov chain. Larger orders get clamped.
+    pub const MAX_ORDER: usize = 16;
+
+    /// Create a new Markov chain of the given order from a sequence of events, and an optional
+    /// seed for the random number generator. The order gets clamped to [`Self::MAX_ORDER`].
+    ///
+    /// ### Panics
+    /// Panics when the given order is 0.
+    pub fn new(sequence: Vec<Event>, order: usize, seed: Option<[u8; 32]>) -> Self {
+        assert!(order > 0, "Invalid order: order must be > 0");
+        let order = order.min(Self::MAX_ORDER);
+        // map sequence events to unique event indices
+        let mut events: Vec<Event> = vec![];
+        let mut indices = Vec::with_capacity(sequence.len());
+        for event in sequence {
+            if let Some(index) = events.iter().position(|e| *e == event) {
+                indices.push(index);
+            } else {
+                indices.push(events.len());
+                events.push(event);
+            }
+        }
+        // count transitions from states of `order` events to the next event in the loop
+        let mut counts = BTreeMap::<Vec<usize>, BTreeMap<usize, usize>>::new();
+        for start in 0..indices.len() {
+            let state = (start..start + order)
+                .map(|index| indices[index % indices.len()])
+                .collect::<Vec<_>>();
+            let next = indices[(start + order) % indices.len()];
+            *counts.entry(state).or_default().entry(next).or_default() += 1;
+        }
+        let transitions = counts
+            .into_iter()
+            .map(|(state, next)| (state, next.into_iter().collect()))
+            .collect();
+        let initial_state = if indices.is_empty() {
+            vec![]
+        } else {
+            (0..order)
+                .map(|index| indices[index % indices.len()])
+                .collect::<Vec<_>>()
+        };
+        let state = initial_state.clone();
+        let state_index = 0;
+        let rand_seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let rand_gen = Xoshiro256PlusPlus::from_seed(rand_seed);
+        Self {
+            events,
+            transitions,
+            initial_state,
+            order,
+            state,
+            state_index,
+            rand_gen,
+            seed,
+        }
+    }
+
+    /// Get the chain's order.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    // pick the next event index from the transitions of the current state
+    fn next_event_index(&mut self) -> usize {
+        let transitions = &self.transitions[&self.state];
+        let total = transitions.iter().map(|(_, count)| count).sum::<usize>();
+        let mut choice = self.rand_gen.gen_range(0..total);
+        for (next, count) in transitions {
+            if choice < *count {
+                return *next;
+            }
+            choice -= count;
+        }
+        unreachable!("Expecting a transition for each choice");
+    }
+}
+
+impl EventIter for MarkovEventIter {
+    fn set_time_base(&mut self, _time_base: &BeatTimeBase) {
+        // nothing to do
+    }
+
+    fn set_external_context(&mut self, _data: &[(Cow<str>, f64)]) {
+        // nothing to do
+    }
+
+    fn run(
+        &mut self,
+        _pulse: PulseIterItem,
+        _pulse_pattern_length: usize,
+        emit_event: bool,
+    ) -> Option<Event> {
+        if !emit_event || self.events.is_empty() {
+            return None;
+        }
+        // play initial state, then move through the chain
+        let event_index = if self.state_index < self.order {
+            let event_index = self.state[self.state_index];
+            self.state_index += 1;
+            event_index
+        } else {
+            let event_index = self.next_event_index();
+            self.state.remove(0);
+            self.state.push(event_index);
+            event_index
+        };
+        Some(self.events[event_index].clone())
+    }
+
+    fn duplicate(&self) -> Box<dyn EventIter> {
+        Box::new(self.clone())
+    }
+
+    fn reset(&mut self) {
+        self.state = self.initial_state.clone();
+        self.state_index = 0;
+        // reset random number generator to its initial state when the chain is seeded
+        if let Some(seed) = self.seed {
+            self.rand_gen = Xoshiro256PlusPlus::from_seed(seed);
+        }
+        // else create a new random number generator from a random seed
+        else {
+            self.rand_gen = Xoshiro256PlusPlus::from_seed(thread_rng().gen());
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::test_helpers::{note_event, run_events},
+        Note,
+    };
+
+    #[test]
+    fn markov() {
+        let note_events =
+            |notes: &[Note]| -> Vec<Event> { notes.iter().map(|note| note_event(*note)).collect() };
+
+        // deterministic transitions are replayed as they are
+        let sequence = note_events(&[Note::C4, Note::E4, Note::G4, Note::E4]);
+        let mut markov = MarkovEventIter::new(sequence, 2, None);
+        assert_eq!(
+            run_events(&mut markov, 8),
+            note_events(&[
+                Note::C4,
+                Note::E4,
+                Note::G4,
+                Note::E4,
+                Note::C4,
+                Note::E4,
+                Note::G4,
+                Note::E4
+            ])
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>()
+        );
+
+        // random transitions only use learned transitions and are reproducible with seeds
+        let sequence = note_events(&[Note::C4, Note::E4, Note::G4, Note::E4, Note::A4]);
+        let seed = Some([3; 32]);
+        let mut markov = MarkovEventIter::new(sequence.clone(), 1, seed);
+        let events = run_events(&mut markov, 32);
+        assert!(events.iter().all(Option::is_some));
+        for (event, next) in events.iter().flatten().zip(events.iter().flatten().skip(1)) {
+            let learned = sequence
+                .iter()
+                .zip(sequence.iter().cycle().skip(1))
+                .any(|(a, b)| a == event && b == next);
+            assert!(learned, "unexpected transition from {} to {}", event, next);
+        }
+        assert_eq!(
+            events,
+            run_events(&mut MarkovEventIter::new(sequence.clone(), 1, seed), 32)
+        );
+        markov.reset();
+        assert_eq!(events, run_events(&mut markov, 32));
+
+        // empty sequences emit nothing
+        let mut markov = MarkovEventIter::new(vec![], 1, seed);
+        assert!(run_events(&mut markov, 4).iter().all(Option::is_none));
+    }
+}
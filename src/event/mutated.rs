@@ -1,10 +1,12 @@
 use std::{borrow::Cow, fmt::Debug};
 
 use crate::{
-    event::{fixed::FixedEventIter, Event, EventIter},
+    event::{fixed::FixedEventIter, mapped::MappedEventIter, Event, EventIter},
     BeatTimeBase, PulseIterItem,
 };
 
+pub mod mutators;
+
 // -------------------------------------------------------------------------------------------------
 
 /// Pointer to a function which mutates an Event.
@@ -15,6 +17,10 @@ type EventMapFn = dyn FnMut(Event) -> Event + 'static;
 /// Endlessly emits [`Event`] which's value can be mutated in each iter step
 /// with a custom closure.
 ///
+/// Mutations accumulate: the closure gets called with the last mutated event in each step.
+/// [`ToMutatedEventIter::mutate`] instead creates a [`MappedEventIter`], which maps the original
+/// events in each step.
+///
 /// NB: This event iter can not be cloned. `clone_dyn` thus will cause a panic!
 pub struct MutatedEventIter {
     events: Vec<Event>,
@@ -104,19 +110,69 @@ impl EventIter for MutatedEventIter {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Chainable, non-accumulating mutations of event iters. See [`mutators`] for a set of ready-made
+/// mutation closures.
 pub trait ToMutatedEventIter<F>
 where
     F: FnMut(Event) -> Event + Clone + 'static,
 {
-    fn mutate(self, map: F) -> MutatedEventIter;
+    fn mutate(self, map: F) -> MappedEventIter;
 }
 
 impl<F> ToMutatedEventIter<F> for FixedEventIter
 where
     F: FnMut(Event) -> Event + Clone + 'static,
 {
-    /// Upgrade a [`FixedEventIter`] to a [`MutatedEventIter`].
-    fn mutate(self, map: F) -> MutatedEventIter {
-        MutatedEventIter::new(self.events(), map)
+    /// Upgrade a [`FixedEventIter`] to a [`MappedEventIter`], which maps the original events
+    /// with the given closure in each step.
+    fn mutate(self, map: F) -> MappedEventIter {
+        MappedEventIter::new(self.events(), map)
+    }
+}
+
+impl<F> ToMutatedEventIter<F> for MappedEventIter
+where
+    F: FnMut(Event) -> Event + Clone + 'static,
+{
+    /// Chain another mutation to a [`MappedEventIter`], which gets applied after all existing
+    /// mutations. The mutation closures get reset to their initial state.
+    fn mutate(self, map: F) -> MappedEventIter {
+        self.chain(map)
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::event::new_note;
+
+    #[test]
+    fn mutations_accumulate() {
+        let note_event = |note: &str| Event::NoteEvents(vec![new_note(note)]);
+        let mut event_iter = MutatedEventIter::new(vec![note_event("c4")], |mut event| {
+            if let Event::NoteEvents(notes) = &mut event {
+                for note in notes.iter_mut().flatten() {
+                    note.note = note.note.transposed(1);
+                }
+            }
+            event
+        });
+        let pulse = PulseIterItem::default();
+        let run_events = |event_iter: &mut MutatedEventIter| {
+            (0..3)
+                .map(|_| event_iter.run(pulse, 1, true))
+                .collect::<Vec<_>>()
+        };
+        // the first event is mutated up front
+        let expected = vec![
+            Some(note_event("c#4")),
+            Some(note_event("d4")),
+            Some(note_event("d#4")),
+        ];
+        assert_eq!(run_events(&mut event_iter), expected);
+        event_iter.reset();
+        assert_eq!(run_events(&mut event_iter), expected);
     }
 }
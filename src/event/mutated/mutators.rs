@@ -0,0 +1,264 @@
+//! Ready-made mutation closures for [`ToMutatedEventIter::mutate`](super::ToMutatedEventIter).
+//!
+//! All mutators only modify note events. They get applied to the original events in each step,
+//! so their mutations don't accumulate, and can be chained:
+//!
+//! ```rust
+//! use afseq::prelude::*;
+//! use afseq::event::mutated::mutators::{accent, humanize};
+//!
+//! let event_iter = new_note_event_sequence(vec![Some("c4"), Some("e4"), Some("g4")])
+//!     .mutate(humanize(0.2, 0.1, None))
+//!     .mutate(accent(4, 1.5));
+//! ```
+
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::{
+    event::{Event, NoteEvent, RampCurve},
+    Scale,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+// Apply the given function on all note-on events in the event.
+fn map_notes<F>(mut event: Event, mut map: F) -> Event
+where
+    F: FnMut(&mut NoteEvent),
+{
+    if let Event::NoteEvents(notes) = &mut event {
+        for note in notes.iter_mut().flatten() {
+            if note.note.is_note_on() {
+                map(note);
+            }
+        }
+    }
+    event
+}
+
+// Create a new random number generator from the given or a random seed.
+fn new_rand_gen(seed: Option<[u8; 32]>) -> Xoshiro256PlusPlus {
+    Xoshiro256PlusPlus::from_seed(seed.unwrap_or_else(|| thread_rng().gen()))
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Randomly varies the volume and delay of notes.
+///
+/// `volume` is the maximum relative volume change in range \[0 - 1\], `delay` the maximum delay
+/// in range \[0 - 1\] which gets added to the note's delay. An optional seed makes the variations
+/// reproducible.
+///
+/// ### Panics
+/// Panics when the volume or delay amount is out of range.
+pub fn humanize(
+    volume: f32,
+    delay: f32,
+    seed: Option<[u8; 32]>,
+) -> impl FnMut(Event) -> Event + Clone + 'static {
+    assert!(
+        (0.0..=1.0).contains(&volume),
+        "Invalid volume amount: volume must be in range [0 - 1]"
+    );
+    assert!(
+        (0.0..=1.0).contains(&delay),
+        "Invalid delay amount: delay must be in range [0 - 1]"
+    );
+    let mut rand_gen = new_rand_gen(seed);
+    move |event| {
+        map_notes(event, |note| {
+            if volume > 0.0 {
+                note.volume *= 1.0 + rand_gen.gen_range(-volume..=volume);
+            }
+            if delay > 0.0 {
+                note.delay = (note.delay + rand_gen.gen_range(0.0..=delay)).min(1.0);
+            }
+        })
+    }
+}
+
+/// Multiplies the volume of every `every`th note event, starting with the first one, with the
+/// given amount.
+///
+/// ### Panics
+/// Panics when `every` is 0 or the amount is negative.
+pub fn accent(every: usize, amount: f32) -> impl FnMut(Event) -> Event + Clone + 'static {
+    assert!(every > 0, "Invalid accent step: every must be > 0");
+    assert!(amount >= 0.0, "Invalid accent amount: amount must be >= 0");
+    let mut step = 0;
+    move |event| {
+        let accented = step == 0;
+        if matches!(event, Event::NoteEvents(_)) {
+            step = (step + 1) % every;
+        }
+        if accented {
+            map_notes(event, |note| note.volume *= amount)
+        } else {
+            event
+        }
+    }
+}
+
+/// Shapes note volumes with the given curve: volumes in range \[0 - 1\] get mapped through the
+/// curve into the range \[`min` - `max`\].
+pub fn velocity_curve(
+    curve: RampCurve,
+    min: f32,
+    max: f32,
+) -> impl FnMut(Event) -> Event + Clone + 'static {
+    move |event| {
+        map_notes(event, |note| {
+            note.volume = min + (max - min) * curve.apply(note.volume)
+        })
+    }
+}
+
+/// Randomly transposes notes by up to the given number of octaves up or down.
+///
+/// An optional seed makes the transpositions reproducible.
+pub fn randomize_octave(
+    octaves: u8,
+    seed: Option<[u8; 32]>,
+) -> impl FnMut(Event) -> Event + Clone + 'static {
+    let mut rand_gen = new_rand_gen(seed);
+    let octaves = octaves as i32;
+    move |event| {
+        map_notes(event, |note| {
+            note.note = note
+                .note
+                .transposed(12 * rand_gen.gen_range(-octaves..=octaves));
+        })
+    }
+}
+
+/// Transposes notes by the given number of semitones and moves them into the given scale.
+/// See [`Scale::transpose`].
+pub fn transpose_in_scale(
+    scale: Scale,
+    offset: i32,
+) -> impl FnMut(Event) -> Event + Clone + 'static {
+    move |event| map_notes(event, |note| note.note = scale.transpose(note.note, offset))
+}
+
+/// Randomly removes notes: each note is kept with the given probability only.
+///
+/// An optional seed makes the removals reproducible.
+///
+/// ### Panics
+/// Panics when the probability is not in range \[0 - 1\].
+pub fn thin_notes(
+    probability: f32,
+    seed: Option<[u8; 32]>,
+) -> impl FnMut(Event) -> Event + Clone + 'static {
+    assert!(
+        (0.0..=1.0).contains(&probability),
+        "Invalid probability: probability must be in range [0 - 1]"
+    );
+    let mut rand_gen = new_rand_gen(seed);
+    move |mut event| {
+        if let Event::NoteEvents(notes) = &mut event {
+            for note in notes.iter_mut() {
+                if note.is_some() && rand_gen.gen::<f32>() >= probability {
+                    *note = None;
+                }
+            }
+        }
+        event
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::{
+            fixed::ToFixedEventIterSequence, mutated::ToMutatedEventIter, new_note,
+            test_helpers::run_events, EventIter,
+        },
+        Note,
+    };
+
+    fn run_notes(event_iter: &mut dyn EventIter, count: usize) -> Vec<Option<NoteEvent>> {
+        run_events(event_iter, count)
+            .into_iter()
+            .flatten()
+            .flat_map(|event| match event {
+                Event::NoteEvents(notes) => notes,
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mutators() {
+        let sequence = || vec![new_note("c4"), new_note("e4"), new_note("g4")].to_event_sequence();
+
+        // accent and velocity curves
+        let mut event_iter = sequence()
+            .mutate(velocity_curve(RampCurve::Linear, 0.0, 0.5))
+            .mutate(accent(2, 2.0));
+        assert_eq!(
+            run_notes(&mut event_iter, 4)
+                .into_iter()
+                .map(|note| note.unwrap().volume)
+                .collect::<Vec<_>>(),
+            vec![1.0, 0.5, 1.0, 0.5]
+        );
+
+        // chained accents apply to the original events only
+        let mut event_iter = sequence().mutate(accent(1, 2.0));
+        for _ in 0..3 {
+            event_iter = event_iter.mutate(accent(1, 2.0));
+        }
+        assert!(run_notes(&mut event_iter, 30)
+            .into_iter()
+            .all(|note| note.unwrap().volume == 16.0));
+
+        // humanize: mutations don't accumulate and are reproducible
+        let seed = Some([1; 32]);
+        let mut event_iter = sequence().mutate(humanize(0.25, 0.5, seed));
+        let notes = run_notes(&mut event_iter, 30);
+        assert!(notes.iter().flatten().all(|note| {
+            (0.75..=1.25).contains(&note.volume) && (0.0..=0.5).contains(&note.delay)
+        }));
+        assert!(notes.iter().flatten().any(|note| note.volume != 1.0));
+        event_iter.reset();
+        assert_eq!(notes, run_notes(&mut event_iter, 30));
+
+        // octaves and scales
+        let mut event_iter = sequence().mutate(randomize_octave(1, seed));
+        assert!(run_notes(&mut event_iter, 30).iter().flatten().all(|note| [
+            Note::C3,
+            Note::C4,
+            Note::C5,
+            Note::E3,
+            Note::E4,
+            Note::E5,
+            Note::G3,
+            Note::G4,
+            Note::G5
+        ]
+        .contains(&note.note)));
+        let scale = Scale::try_from((Note::C4, "minor")).unwrap();
+        let mut event_iter = sequence().mutate(transpose_in_scale(scale, 2));
+        assert_eq!(
+            run_notes(&mut event_iter, 3)
+                .into_iter()
+                .map(|note| note.unwrap().note)
+                .collect::<Vec<_>>(),
+            vec![Note::D4, Note::F4, Note::Gs4]
+        );
+
+        // thinning
+        let mut event_iter = sequence().mutate(thin_notes(0.0, seed));
+        assert!(run_notes(&mut event_iter, 6).iter().all(Option::is_none));
+        let mut event_iter = sequence().mutate(thin_notes(1.0, seed));
+        assert!(run_notes(&mut event_iter, 6).iter().all(Option::is_some));
+        let mut event_iter = sequence().mutate(thin_notes(0.5, seed));
+        let notes = run_notes(&mut event_iter, 60);
+        assert!(notes.iter().any(Option::is_none) && notes.iter().any(Option::is_some));
+    }
+}
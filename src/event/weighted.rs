@@ -0,0 +1,166 @@
+use std::borrow::Cow;
+
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::{
+    event::{Event, EventIter},
+    BeatTimeBase, PulseIterItem,
+};
+
+// -------------------------------------------------------------------------------------------------
+
+/// Emits a randomly chosen [`Event`] from a list of weighted events in each pulse.
+///
+/// The probability of an event to get picked is its weight relative to the sum of all weights.
+/// Events with a weight of 0 never get picked.
+#[derive(Clone, Debug)]
+pub struct WeightedChoiceEventIter {
+    events: Vec<Event>,
+    weights: Vec<f32>,
+    total_weight: f32,
+    rand_gen: Xoshiro256PlusPlus,
+    seed: Option<[u8; 32]>,
+}
+
+impl WeightedChoiceEventIter {
+    /// Create a new weighted choice from the given event and weight pairs, and an optional seed
+    /// for the random number generator.
+    ///
+    /// ### Panics
+    /// Panics when a weight is negative or not finite, or when the sum of all weights is not
+    /// finite.
+    pub fn new(choices: Vec<(Event, f32)>, seed: Option<[u8; 32]>) -> Self {
+        assert!(
+            choices
+                .iter()
+                .all(|(_, weight)| weight.is_finite() && *weight >= 0.0),
+            "Invalid weights: weights must be >= 0"
+        );
+        let (events, weights): (Vec<_>, Vec<_>) = choices.into_iter().unzip();
+        let total_weight: f32 = weights.iter().sum();
+        assert!(
+            total_weight.is_finite(),
+            "Invalid weights: the sum of all weights must be finite"
+        );
+        let rand_seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let rand_gen = Xoshiro256PlusPlus::from_seed(rand_seed);
+        Self {
+            events,
+            weights,
+            total_weight,
+            rand_gen,
+            seed,
+        }
+    }
+
+    // pick a random event index, respecting the event weights
+    fn next_event_index(&mut self) -> usize {
+        let mut choice = self.rand_gen.gen_range(0.0..self.total_weight);
+        for (index, weight) in self.weights.iter().enumerate() {
+            if choice < *weight {
+                return index;
+            }
+            choice -= weight;
+        }
+        // rounding errors: pick the last event with a weight
+        self.weights
+            .iter()
+            .rposition(|weight| *weight > 0.0)
+            .expect("Expecting at least one event with a weight")
+    }
+}
+
+impl EventIter for WeightedChoiceEventIter {
+    fn set_time_base(&mut self, _time_base: &BeatTimeBase) {
+        // nothing to do
+    }
+
+    fn set_external_context(&mut self, _data: &[(Cow<str>, f64)]) {
+        // nothing to do
+    }
+
+    fn run(
+        &mut self,
+        _pulse: PulseIterItem,
+        _pulse_pattern_length: usize,
+        emit_event: bool,
+    ) -> Option<Event> {
+        if !emit_event || self.total_weight <= 0.0 {
+            return None;
+        }
+        let event_index = self.next_event_index();
+        Some(self.events[event_index].clone())
+    }
+
+    fn duplicate(&self) -> Box<dyn EventIter> {
+        Box::new(self.clone())
+    }
+
+    fn reset(&mut self) {
+        // reset random number generator to its initial state when the choice is seeded
+        if let Some(seed) = self.seed {
+            self.rand_gen = Xoshiro256PlusPlus::from_seed(seed);
+        }
+        // else create a new random number generator from a random seed
+        else {
+            self.rand_gen = Xoshiro256PlusPlus::from_seed(thread_rng().gen());
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::test_helpers::{note_event, run_events},
+        Note,
+    };
+
+    #[test]
+    fn weighted_choice() {
+        let choices = vec![
+            (note_event(Note::C4), 5.0),
+            (note_event(Note::E4), 3.0),
+            (note_event(Note::G4), 2.0),
+            (note_event(Note::B4), 0.0),
+        ];
+
+        // weights are respected and choices are reproducible with seeds
+        let seed = Some([7; 32]);
+        let mut choice = WeightedChoiceEventIter::new(choices.clone(), seed);
+        let events = run_events(&mut choice, 1000);
+        assert!(events.iter().all(Option::is_some));
+        let count = |note| {
+            let event = Some(note_event(note));
+            events.iter().filter(|e| **e == event).count()
+        };
+        assert!((400..600).contains(&count(Note::C4)));
+        assert!((200..400).contains(&count(Note::E4)));
+        assert!((100..300).contains(&count(Note::G4)));
+        assert_eq!(count(Note::B4), 0);
+        assert_eq!(
+            events,
+            run_events(&mut WeightedChoiceEventIter::new(choices, seed), 1000)
+        );
+        choice.reset();
+        assert_eq!(events, run_events(&mut choice, 1000));
+
+        // no or only zero weighted choices emit nothing
+        let mut choice = WeightedChoiceEventIter::new(vec![], seed);
+        assert!(run_events(&mut choice, 4).iter().all(Option::is_none));
+        let mut choice = WeightedChoiceEventIter::new(vec![(note_event(Note::C4), 0.0)], seed);
+        assert!(run_events(&mut choice, 4).iter().all(Option::is_none));
+    }
+
+    #[test]
+    #[should_panic(expected = "the sum of all weights must be finite")]
+    fn overflowing_weights() {
+        let _ = WeightedChoiceEventIter::new(
+            vec![(note_event(Note::C4), 3e38), (note_event(Note::E4), 3e38)],
+            None,
+        );
+    }
+}
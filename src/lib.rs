@@ -34,6 +34,9 @@ pub use chord::Chord;
 pub mod scale;
 pub use scale::Scale;
 
+pub mod tuning;
+pub use tuning::Tuning;
+
 pub mod event;
 pub use event::{Event, EventIter};
 
@@ -104,15 +104,75 @@ impl Phrase {
 
     /// Run rhythms until a given sample time is reached, calling the given `visitor`
     /// function for all emitted events to consume them.
+    ///
+    /// Emitted [`TempoChangeEvent`](crate::event::TempoChangeEvent)s get applied to all rhythms
+    /// at the event's sample time.
     pub fn emit_until_time<F>(&mut self, run_until_time: SampleTime, consumer: &mut F)
+    where
+        F: FnMut(RhythmIndex, SampleTime, Option<Event>, SampleTime),
+    {
+        while self
+            .emit_until_time_or_tempo_change(run_until_time, consumer)
+            .is_some()
+        {
+            // continue with the new time base
+        }
+    }
+
+    /// Run rhythms until a given sample time is reached or a tempo change event got emitted.
+    /// Tempo changes get applied to all rhythms in the phrase and returned as tuple of the
+    /// event's sample time and the new time base, so the caller can continue running the phrase
+    /// or can apply the change to other phrases as well.
+    pub(crate) fn emit_until_time_or_tempo_change<F>(
+        &mut self,
+        run_until_time: SampleTime,
+        consumer: &mut F,
+    ) -> Option<(SampleTime, BeatTimeBase)>
     where
         F: FnMut(RhythmIndex, SampleTime, Option<Event>, SampleTime),
     {
         // emit next events until we've reached the desired sample_time
         while let Some((rhythm_index, event)) = self.next_event_until_time(run_until_time) {
             debug_assert!(event.time < run_until_time);
+            let tempo_change = match &event.event {
+                Some(Event::TempoChangeEvent(change)) => Some(*change),
+                _ => None,
+            };
             consumer(rhythm_index, event.time, event.event, event.duration);
+            if let Some(change) = tempo_change {
+                let time_base = change.apply(&self.time_base);
+                self.set_time_base_at_time(&time_base, event.time);
+                return Some((event.time, time_base));
+            }
+        }
+        None
+    }
+
+    /// Apply a new time base to all rhythms at the given sample time: rhythm events which got
+    /// scheduled after the given time get rescheduled to the new time base.
+    pub(crate) fn set_time_base_at_time(&mut self, time_base: &BeatTimeBase, time: SampleTime) {
+        let rhythm_time = time.saturating_sub(self.sample_offset);
+        for (rhythm_slot, next_event) in self.rhythm_slots.iter().zip(self.next_events.iter_mut()) {
+            if let RhythmSlot::Rhythm(rhythm) = rhythm_slot {
+                let mut rhythm = rhythm.borrow_mut();
+                // move the rhythm's current time to the change's time. Events before the given
+                // time already got fetched, so this never emits anything.
+                let pending_event = rhythm.run_until_time(rhythm_time);
+                debug_assert!(pending_event.is_none(), "unexpected pending rhythm event");
+                let previous_step_length = rhythm.pattern_step_length();
+                rhythm.set_time_base(time_base);
+                // reschedule prefetched events which are due with or after the change
+                let ratio = rhythm.pattern_step_length() / previous_step_length;
+                if let Some((_, event)) = next_event {
+                    if event.time >= rhythm_time {
+                        let delta = (event.time - rhythm_time) as f64 * ratio;
+                        event.time = rhythm_time + delta as SampleTime;
+                        event.duration = (event.duration as f64 * ratio) as SampleTime;
+                    }
+                }
+            }
         }
+        self.time_base = *time_base;
     }
 
     /// reset playback status and shift events to the given sample position.
@@ -246,6 +306,7 @@ impl Rhythm for Phrase {
                 rhythm.borrow_mut().set_time_base(time_base);
             }
         }
+        self.time_base = *time_base;
     }
 
     fn set_instrument(&mut self, instrument: Option<InstrumentId>) {
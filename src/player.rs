@@ -17,7 +17,7 @@ use afplay::{
 use crate::{
     event::{unique_instrument_id, InstrumentId},
     time::TimeBase,
-    Event, Note, RhythmIter, SampleTime, Sequence,
+    Event, Note, RhythmIter, SampleTime, Sequence, Tuning,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -37,9 +37,13 @@ const PLAYBACK_PRELOAD_SECONDS: f64 = 0.5;
 ///
 /// When files are accessed, the stored file sources are cloned, which avoids loading and decoding
 /// the files again. Cloned [`PreloadedFileSource`] are using a shared Buffer, so cloning is very cheap.
+///
+/// Samples can optionally be registered by name via `load_named_sample`. Names then can be
+/// resolved to instrument ids via `named_sample_id`, e.g. to play back names in Tidal cycles.
 #[derive(Default)]
 pub struct SamplePool {
     pool: RwLock<HashMap<InstrumentId, PreloadedFileSource>>,
+    names: RwLock<HashMap<String, Vec<InstrumentId>>>,
 }
 
 impl SamplePool {
@@ -47,6 +51,7 @@ impl SamplePool {
     pub fn new() -> Self {
         Self {
             pool: RwLock::new(HashMap::new()),
+            names: RwLock::new(HashMap::new()),
         }
     }
 
@@ -87,6 +92,36 @@ impl SamplePool {
         pool.insert(id, sample);
         Ok(id)
     }
+
+    /// Load a sample file into a [`PreloadedFileSource`], register it with the given name and
+    /// return its id. Loading multiple samples with the same name adds new variants to the name:
+    /// the first sample is variant 0, the second one variant 1 and so on.
+    ///
+    /// ### Errors
+    /// Returns an error if the sample file could not be loaded.
+    ///
+    /// ### Panics
+    /// Panics if the sample pool can not be accessed
+    pub fn load_named_sample(&self, file_path: &str, name: &str) -> Result<InstrumentId, Error> {
+        let id = self.load_sample(file_path)?;
+        let mut names = self.names.write().expect("Failed to access sample pool");
+        names.entry(name.to_string()).or_default().push(id);
+        Ok(id)
+    }
+
+    /// Resolve a sample name and variant index to a sample id. Variant indices wrap around
+    /// the number of registered variants, so `"bd:3"` picks the second sample when only two
+    /// samples got registered as `bd`.
+    ///
+    /// Returns None if no sample got registered with the given name.
+    ///
+    /// ### Panics
+    /// Panics if the sample pool can not be accessed
+    pub fn named_sample_id(&self, name: &str, variant: usize) -> Option<InstrumentId> {
+        let names = self.names.read().expect("Failed to access sample pool");
+        let ids = names.get(name).filter(|ids| !ids.is_empty())?;
+        Some(ids[variant % ids.len()])
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -125,6 +160,27 @@ impl SamplePlaybackContext {
 
 // -------------------------------------------------------------------------------------------------
 
+/// Playback speed of a sample with the given note, resolved through the given tuning or 12-TET.
+/// Returns None for notes which are not mapped in the tuning.
+fn note_playback_speed(tuning: Option<&Tuning>, note: Note) -> Option<f64> {
+    match tuning {
+        Some(tuning) => tuning.playback_speed(note),
+        None => Some(speed_from_note(note as u8)),
+    }
+}
+
+/// Convert a glide over the given amount of semitones and seconds into a glide rate in
+/// semitones per second. Returns None for zero durations, which change the pitch immediately.
+fn glide_semitones_per_sec(semitones: f32, duration: f64) -> Option<f32> {
+    if duration > 0.0 && semitones > 0.0 {
+        Some((semitones as f64 / duration) as f32)
+    } else {
+        None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
 /// An simple example player implementation, which plays back a `Sequence` via the `afplay` crate
 /// using the default audio output device using plain samples loaded from a file as instruments.
 ///
@@ -133,6 +189,8 @@ pub struct SamplePlayer {
     player: AudioFilePlayer,
     sample_pool: Arc<RwLock<SamplePool>>,
     playing_notes: Vec<HashMap<usize, (AudioFilePlaybackId, Note)>>,
+    pitch_bends: Vec<f64>,
+    tuning: Option<Tuning>,
     new_note_action: NewNoteAction,
     playback_pos_emit_rate: Duration,
     show_events: bool,
@@ -155,6 +213,8 @@ impl SamplePlayer {
         let audio_output = DefaultAudioOutput::open()?;
         let player = AudioFilePlayer::new(audio_output.sink(), playback_status_sender);
         let playing_notes = Vec::new();
+        let pitch_bends = Vec::new();
+        let tuning = None;
         let new_note_action = NewNoteAction::Continue;
         let playback_pos_emit_rate = Duration::from_secs(1);
         let show_events = false;
@@ -165,6 +225,8 @@ impl SamplePlayer {
             player,
             sample_pool,
             playing_notes,
+            pitch_bends,
+            tuning,
             new_note_action,
             playback_pos_emit_rate,
             show_events,
@@ -208,6 +270,16 @@ impl SamplePlayer {
         self.new_note_action = action;
     }
 
+    /// get current tuning, if any.
+    pub fn tuning(&self) -> Option<&Tuning> {
+        self.tuning.as_ref()
+    }
+    /// by default None: set a tuning to resolve note pitches through the tuning instead of 12-TET.
+    /// Notes which are not mapped in the tuning are not played.
+    pub fn set_tuning(&mut self, tuning: Option<Tuning>) {
+        self.tuning = tuning;
+    }
+
     /// Run/play the given sequence until it stops.
     pub fn run(
         &mut self,
@@ -236,6 +308,7 @@ impl SamplePlayer {
             // match playing notes state to the passed rhythm
             self.playing_notes
                 .resize(sequence.rhythm_slot_count(), HashMap::new());
+            self.pitch_bends.resize(sequence.rhythm_slot_count(), 1.0);
             // seek new phase to our previously played time
             self.seek_sequence_until_time(sequence, self.emitted_sample_time);
             log::debug!(target: "Player",
@@ -286,6 +359,8 @@ impl SamplePlayer {
         self.playing_notes.clear();
         self.playing_notes
             .resize(sequence.rhythm_slot_count(), HashMap::new());
+        self.pitch_bends.clear();
+        self.pitch_bends.resize(sequence.rhythm_slot_count(), 1.0);
         // stop whatever is playing in case we're restarting
         self.player
             .stop_all_sources()
@@ -327,9 +402,65 @@ impl SamplePlayer {
                 }
                 // play
                 let playing_notes_in_rhythm = &mut self.playing_notes[rhythm_index];
-                if let Some(Event::NoteEvents(notes)) = event {
+                let pitch_bend = &mut self.pitch_bends[rhythm_index];
+                if let Some(Event::PitchBendEvent(bend)) = event {
+                    // bend all playing notes in this rhythm and apply the bend to new notes
+                    let previous_pitch_bend = *pitch_bend;
+                    *pitch_bend = bend.speed_factor();
+                    // glide from the previous bend to the new one
+                    let semitones = (12.0 * (*pitch_bend / previous_pitch_bend).log2()).abs();
+                    let glide_duration = bend.glide.map(|glide| {
+                        glide as f64 * event_duration as f64
+                            / self.player.output_sample_rate() as f64
+                    });
+                    for (playback_id, note) in playing_notes_in_rhythm.values() {
+                        let glide = glide_duration.and_then(|duration| {
+                            glide_semitones_per_sec(semitones as f32, duration)
+                        });
+                        if let Some(speed) = note_playback_speed(self.tuning.as_ref(), *note) {
+                            if let Err(_err) = self.player.set_source_speed_at_sample_time(
+                                *playback_id,
+                                speed * *pitch_bend,
+                                glide,
+                                start_offset + sample_time,
+                            ) {
+                                // this is expected when the sample played to end
+                            }
+                        }
+                    }
+                } else if let Some(Event::NoteEvents(notes)) = event {
                     for (voice_index, note_event) in notes.iter().enumerate() {
                         if let Some(note_event) = note_event {
+                            // glide playing samples on this voice channel to the new note
+                            if let (Some(glide), Some((playback_id, playing_note)), Some(speed)) = (
+                                note_event.glide,
+                                playing_notes_in_rhythm.get(&voice_index).copied(),
+                                note_playback_speed(self.tuning.as_ref(), note_event.note),
+                            ) {
+                                if note_event.note.is_note_on() {
+                                    let sample_delay = (note_event.delay
+                                        * event_duration as f32)
+                                        as SampleTime;
+                                    let glide_duration = glide as f64 * event_duration as f64
+                                        / self.player.output_sample_rate() as f64;
+                                    // glide in the tuning's pitch space, not in key numbers
+                                    let playing_speed =
+                                        note_playback_speed(self.tuning.as_ref(), playing_note)
+                                            .unwrap_or(speed);
+                                    let semitones = (12.0 * (speed / playing_speed).log2()).abs();
+                                    if let Err(_err) = self.player.set_source_speed_at_sample_time(
+                                        playback_id,
+                                        speed * *pitch_bend,
+                                        glide_semitones_per_sec(semitones as f32, glide_duration),
+                                        start_offset + sample_time + sample_delay,
+                                    ) {
+                                        // this is expected when the sample played to end
+                                    }
+                                    playing_notes_in_rhythm
+                                        .insert(voice_index, (playback_id, note_event.note));
+                                    continue;
+                                }
+                            }
                             // stop playing samples on this voice channel
                             if let Some((playback_id, _)) =
                                 playing_notes_in_rhythm.get(&voice_index)
@@ -348,9 +479,12 @@ impl SamplePlayer {
                             }
                             // start a new sample - when this is a note off, we already stopped it above
                             if note_event.note.is_note_on() {
-                                if let Some(instrument) = note_event.instrument {
+                                if let (Some(instrument), Some(speed)) = (
+                                    note_event.instrument,
+                                    note_playback_speed(self.tuning.as_ref(), note_event.note),
+                                ) {
                                     let playback_options = FilePlaybackOptions::default()
-                                        .speed(speed_from_note(note_event.note as u8))
+                                        .speed(speed * *pitch_bend)
                                         .playback_pos_emit_rate(self.playback_pos_emit_rate);
                                     let playback_sample_rate = self.player.output_sample_rate();
                                     let sample_pool = self
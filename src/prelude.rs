@@ -10,16 +10,31 @@
 pub use super::{
     // all public types to create event iters, gates and patterns
     event::{
-        fixed::ToFixedEventIter, fixed::ToFixedEventIterSequence, mutated::ToMutatedEventIter,
+        arpeggiator::ArpeggiatorEventIter,
+        arpeggiator::ArpeggiatorMode,
+        fixed::ToFixedEventIter,
+        fixed::ToFixedEventIterSequence,
+        layered::LayeredEventIter,
+        mapped::MappedEventIter,
+        markov::MarkovEventIter,
+        mutated::mutators::{
+            accent, humanize, randomize_octave, thin_notes, transpose_in_scale, velocity_curve,
+        },
+        mutated::ToMutatedEventIter,
         new_empty_note, new_empty_note_event, new_note, new_note_event, new_note_event_sequence,
-        new_parameter_change_event, new_polyphonic_note_event, new_polyphonic_note_sequence_event,
-        unique_instrument_id, InstrumentId, NoteEvent, ParameterChangeEvent, ParameterId,
+        new_parameter_change_event, new_parameter_envelope_event, new_parameter_ramp_event,
+        new_pitch_bend_event, new_polyphonic_note_event, new_polyphonic_note_sequence_event,
+        new_tempo_change_event, unique_instrument_id,
+        weighted::WeightedChoiceEventIter,
+        InstrumentId, NoteEvent, ParameterChangeEvent, ParameterId, ParameterRamp, PitchBendEvent,
+        RampCurve, TempoChangeEvent,
     },
     gate::ProbabilityGate,
     pattern::{euclidean, fixed::ToFixedPattern},
     phrase::RhythmSlot,
     rhythm::{beat_time::BeatTimeRhythm, second_time::SecondTimeRhythm},
     time::{BeatTimeStep, SecondTimeStep},
+    tuning::KeyboardMapping,
     // all public basic types
     BeatTimeBase,
     Chord,
@@ -40,8 +55,13 @@ pub use super::{
     SecondTimeBase,
     Sequence,
     TimeBase,
+    Tuning,
 };
 
+#[cfg(feature = "tidal")]
+// all public tidal types
+pub use super::event::cycle::{CycleEventIter, CycleParseError};
+
 #[cfg(feature = "scripting")]
 // all public scripting types
 pub use super::{
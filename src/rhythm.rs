@@ -17,7 +17,7 @@ pub mod beat_time;
 pub mod second_time;
 
 #[cfg(feature = "tidal")]
-mod tidal;
+pub(crate) mod tidal;
 
 // -------------------------------------------------------------------------------------------------
 
@@ -11,11 +11,11 @@ use std::{
 use std::borrow::BorrowMut;
 
 use crate::{
-    event::{fixed::FixedEventIter, Event, EventIter, InstrumentId},
+    event::{fixed::FixedEventIter, Event, EventIter, InstrumentId, NoteEvent},
     gate::ProbabilityGate,
     pattern::{fixed::FixedPattern, Pattern},
     time::{BeatTimeBase, SampleTimeDisplay},
-    Gate, Rhythm, RhythmIter, RhythmIterItem, SampleTime,
+    Gate, Note, Rhythm, RhythmIter, RhythmIterItem, SampleTime,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -36,6 +36,9 @@ pub trait GenericRhythmTimeStep: Debug + Clone + Copy + 'static {
 /// Generic `Rhythm` impl which uses a [`Pattern`] to generate pulse events, filtered by a [`Gate`]
 /// which then drives an [`EventIter`][`crate::EventIter`].
 ///
+/// Note events with a length get stopped with a note-off event on their voice when their length
+/// elapsed. Such note-off events are emitted as separate items with a duration of 0.
+///
 /// Internal time units are generics, and will usually be beats or seconds.
 #[derive(Debug)]
 pub struct GenericRhythm<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> {
@@ -48,6 +51,7 @@ pub struct GenericRhythm<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeS
     event_iter: Box<dyn EventIter>,
     event_iter_sample_time: SampleTime,
     event_iter_next_sample_time: f64,
+    pending_note_offs: Vec<(f64, usize)>,
     sample_offset: SampleTime,
 }
 
@@ -62,6 +66,7 @@ impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> GenericRhythm<S
         let event_iter = Box::<FixedEventIter>::default();
         let event_iter_sample_time = 0;
         let event_iter_next_sample_time = offset.to_samples(&time_base);
+        let pending_note_offs = Vec::new();
         let sample_offset = 0;
         Self {
             time_base,
@@ -73,6 +78,7 @@ impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> GenericRhythm<S
             event_iter,
             event_iter_sample_time,
             event_iter_next_sample_time,
+            pending_note_offs,
             sample_offset,
         }
     }
@@ -193,6 +199,56 @@ impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> GenericRhythm<S
             event
         }
     }
+
+    /// Schedule note-offs for all note events with a length in the given event, which got
+    /// emitted at the given sample time with the given duration.
+    fn schedule_note_offs(&mut self, event: &Option<Event>, time: f64, duration: f64) {
+        if let Some(Event::NoteEvents(note_events)) = event {
+            for (voice_index, note_event) in note_events.iter().enumerate() {
+                if let Some(note_event) = note_event {
+                    // new notes replace pending note-offs on the same voice
+                    let note_time = time + note_event.delay as f64 * duration;
+                    self.pending_note_offs
+                        .retain(|(off_time, voice)| *voice != voice_index || *off_time < note_time);
+                    if let Some(length) = note_event.length {
+                        if note_event.note.is_note_on() {
+                            let off_time = note_time + length as f64 * duration;
+                            self.pending_note_offs.push((off_time, voice_index));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Emit all pending note-offs with the smallest sample time as a single event, when they are
+    /// due before the given sample time and are not scheduled after the given `until` time.
+    fn next_note_offs(&mut self, sample_time: SampleTime, until: f64) -> Option<RhythmIterItem> {
+        let time = self
+            .pending_note_offs
+            .iter()
+            .map(|(off_time, _)| *off_time)
+            .reduce(f64::min)?;
+        if time > until || self.sample_offset + time as SampleTime >= sample_time {
+            return None;
+        }
+        let mut note_events = vec![];
+        self.pending_note_offs.retain(|(off_time, voice_index)| {
+            if *off_time > time {
+                return true;
+            }
+            if note_events.len() <= *voice_index {
+                note_events.resize(*voice_index + 1, None);
+            }
+            note_events[*voice_index] = Some(NoteEvent::from(Note::OFF));
+            false
+        });
+        Some(RhythmIterItem {
+            time: self.sample_offset + time as SampleTime,
+            event: Some(Event::NoteEvents(note_events)),
+            duration: 0,
+        })
+    }
 }
 
 impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> Clone
@@ -203,6 +259,7 @@ impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> Clone
             pattern: self.pattern.duplicate(),
             event_iter: self.event_iter.duplicate(),
             gate: self.gate.duplicate(),
+            pending_note_offs: self.pending_note_offs.clone(),
             ..*self
         }
     }
@@ -233,8 +290,13 @@ impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> RhythmIter
     }
 
     fn run_until_time(&mut self, sample_time: SampleTime) -> Option<RhythmIterItem> {
-        // check if the next event is scheduled before the given target time
+        // check if pending note-offs are due before or with the next event
         self.event_iter_sample_time = sample_time;
+        if let Some(note_offs) = self.next_note_offs(sample_time, self.event_iter_next_sample_time)
+        {
+            return Some(note_offs);
+        }
+        // check if the next event is scheduled before the given target time
         let next_sample_time = self.sample_offset + self.event_iter_next_sample_time as SampleTime;
         if next_sample_time >= sample_time {
             // next event is not yet due
@@ -246,18 +308,21 @@ impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> RhythmIter
                 let emit_event = self.gate.run(&pulse);
                 (pulse, emit_event)
             } else {
-                // pattern playback finished
-                return None;
+                // pattern playback finished: flush remaining note-offs
+                return self.next_note_offs(sample_time, f64::MAX);
             }
         };
         // generate an event from the event iter
         let pulse_pattern_length = self.pattern.len();
         let mut event = self.event_iter.run(pulse, pulse_pattern_length, emit_event);
         event = self.event_with_default_instrument(event);
+        // schedule note-offs for notes with lengths
+        let step_duration = self.step.to_samples(&self.time_base) * pulse.step_time;
+        self.schedule_note_offs(&event, self.event_iter_next_sample_time, step_duration);
         // return event as sample timed rhythm iter item
         let time = self.sample_offset + self.event_iter_next_sample_time as SampleTime;
-        let duration = (self.step.to_samples(&self.time_base) * pulse.step_time) as SampleTime;
-        self.event_iter_next_sample_time += self.step.to_samples(&self.time_base) * pulse.step_time;
+        let duration = step_duration as SampleTime;
+        self.event_iter_next_sample_time += step_duration;
         Some(RhythmIterItem {
             time,
             event,
@@ -279,11 +344,22 @@ impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> Rhythm
 
     fn set_time_base(&mut self, time_base: &BeatTimeBase) {
         // reschedule next event's sample time to the new time base
-        if self.event_iter_sample_time > 0 {
-            self.event_iter_next_sample_time = self.event_iter_sample_time as f64
-                + (self.event_iter_next_sample_time - self.event_iter_sample_time as f64)
-                    / self.step.to_samples(&self.time_base)
-                    * self.step.to_samples(time_base);
+        // NB: event_iter_sample_time includes the sample offset, scheduled times do not
+        let current_time = self
+            .event_iter_sample_time
+            .saturating_sub(self.sample_offset) as f64;
+        let started = self.event_iter_sample_time > 0
+            || self.event_iter_next_sample_time > self.offset.to_samples(&self.time_base);
+        if started {
+            let reschedule = |sample_time: f64| {
+                current_time
+                    + (sample_time - current_time) / self.step.to_samples(&self.time_base)
+                        * self.step.to_samples(time_base)
+            };
+            self.event_iter_next_sample_time = reschedule(self.event_iter_next_sample_time);
+            for (off_time, _) in &mut self.pending_note_offs {
+                *off_time = reschedule(*off_time);
+            }
         }
         self.time_base = *time_base;
         // update pattern, gate and event iter
@@ -312,6 +388,7 @@ impl<Step: GenericRhythmTimeStep, Offset: GenericRhythmTimeStep> Rhythm
         self.event_iter.reset();
         self.event_iter_sample_time = 0;
         self.event_iter_next_sample_time = self.offset.to_samples(&self.time_base);
+        self.pending_note_offs.clear();
         self.pattern.reset();
         self.gate.reset();
     }
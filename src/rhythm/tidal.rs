@@ -4,17 +4,27 @@ use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
 use rand::{thread_rng, Rng, SeedableRng};
 use rand_xoshiro::Xoshiro256PlusPlus;
-use fraction::{Fraction, One, Zero};
+use fraction::{Fraction, One, ToPrimitive, Zero};
+
+mod error;
+mod transform;
+pub use error::ParseError;
+use transform::Source;
 
 type F = fraction::Fraction;
 
 #[derive(Clone, Debug, PartialEq)]
-struct Pitch {
+pub(crate) struct Pitch {
     note: u8,
     octave: u8,
 }
 
 impl Pitch {
+    // midi note value of the pitch, clamped to the valid midi note range
+    pub(crate) fn midi_note(&self) -> u8 {
+        (self.octave as u32 * 12 + self.note as u32).min(0x7f) as u8
+    }
+
     fn as_note_value(note: char) -> Option<u8> {
         match note {
             'c' => Some(0),
@@ -66,6 +76,105 @@ impl Pitch {
     }
 }
 
+impl Display for Pitch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const NOTE_NAMES: [&str; 12] = [
+            "c", "c#", "d", "d#", "e", "f", "f#", "g", "g#", "a", "a#", "b",
+        ];
+        let note = NOTE_NAMES[(self.note % 12) as usize];
+        f.write_fmt(format_args!("{}{}", note, self.octave))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Chord {
+    pitch: Pitch,
+    mode: String,
+    inversion: u8,
+    voicing: Option<u8>,
+}
+
+impl Chord {
+    // root pitch of the chord
+    pub(crate) fn pitch(&self) -> &Pitch {
+        &self.pitch
+    }
+
+    // chord mode name, a key in `chord::chords()`
+    pub(crate) fn mode(&self) -> &str {
+        &self.mode
+    }
+
+    // number of inversions, which move the lowest note of the chord an octave up
+    pub(crate) fn inversion(&self) -> u8 {
+        self.inversion
+    }
+
+    // number of notes the chord should be expanded or shrunk to, if any
+    pub(crate) fn voicing(&self) -> Option<u8> {
+        self.voicing
+    }
+
+    fn parse(pair: Pair<Rule>) -> Result<Chord, ParseError> {
+        let mut chord = Chord {
+            pitch: Pitch::parse(pair.clone()),
+            mode: String::new(),
+            inversion: 0,
+            voicing: None,
+        };
+        let mut mode_position = pair.as_span().start_pos();
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::chord_mode => {
+                    chord.mode = p.as_str().to_string();
+                    mode_position = p.as_span().start_pos();
+                }
+                Rule::chord_inversion => {
+                    chord.inversion = chord.inversion.saturating_add(p.as_str().len() as u8)
+                }
+                Rule::chord_voicing => {
+                    // there are no more than 128 distinct midi notes
+                    const MAX_VOICING: u8 = 128;
+                    match p.as_str().parse::<u8>() {
+                        Ok(voicing) if voicing <= MAX_VOICING => chord.voicing = Some(voicing),
+                        _ => {
+                            return Err(ParseError::at(
+                                &p,
+                                format!("invalid chord voicing: max {} notes", MAX_VOICING),
+                            ))
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        if crate::chord::chord_intervals(&chord.mode).is_empty() {
+            return Err(ParseError::new(
+                mode_position,
+                format!(
+                    "unknown chord mode '{}', valid modes are: {}",
+                    chord.mode,
+                    crate::chord::chord_names()
+                ),
+            ));
+        }
+        Ok(chord)
+    }
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}'{}", self.pitch, self.mode))?;
+        if self.inversion > 0 {
+            f.write_fmt(format_args!("'{}", "i".repeat(self.inversion as usize)))?;
+        }
+        if let Some(voicing) = self.voicing {
+            f.write_fmt(format_args!("'{}", voicing))?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 enum Step {
     Single(Single),
@@ -79,14 +188,104 @@ enum Step {
 }
 
 impl Step {
-    fn parse_single(single: Pair<Rule>) -> Result<Step, String> {
+    fn parse_single(single: Pair<Rule>) -> Result<Step, ParseError> {
+        let position = single.as_span().start_pos();
         match single.into_inner().next() {
             Some(pair) => {
                 let string = pair.as_str().to_string();
                 let value = Value::parse(pair)?;
                 Ok(Step::Single(Single { string, value }))
             }
-            None => Err("empty single".to_string()),
+            None => Err(ParseError::new(position, "empty single")),
+        }
+    }
+
+    // steps of groups as mini-notation without the group's brackets, other steps as they are
+    fn inner_notation(&self) -> String {
+        match self {
+            Step::Subdivision(Subdivision { steps })
+            | Step::Alternating(Alternating { steps })
+            | Step::Polymeter(Polymeter { steps, .. }) => Step::join(steps, " "),
+            Step::Stack(st) => Step::join_inner(&st.stack, ", "),
+            _ => self.to_string(),
+        }
+    }
+
+    // target values are no notes: convert pitches and chords to names with their original
+    // strings, so they print back as they were written
+    fn convert_targets_to_names(&mut self) {
+        match self {
+            Step::Single(s) => {
+                if matches!(s.value, Value::Pitch(_) | Value::Chord(_)) {
+                    s.value = Value::Name(s.string.clone());
+                }
+            }
+            Step::Subdivision(Subdivision { steps })
+            | Step::Alternating(Alternating { steps })
+            | Step::Polymeter(Polymeter { steps, .. }) => {
+                steps.iter_mut().for_each(Step::convert_targets_to_names)
+            }
+            Step::Stack(st) => st.stack.iter_mut().for_each(Step::convert_targets_to_names),
+            Step::Choices(cs) => cs
+                .choices
+                .iter_mut()
+                .for_each(Step::convert_targets_to_names),
+            Step::Expression(e) => e.left.convert_targets_to_names(),
+            Step::Bjorklund(b) => b.left.convert_targets_to_names(),
+        }
+    }
+
+    fn join(steps: &[Step], separator: &str) -> String {
+        steps
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    fn join_inner(steps: &[Step], separator: &str) -> String {
+        steps
+            .iter()
+            .map(|s| s.inner_notation())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+// normalized mini-notation of the step, which parses back to an equivalent step
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Single(s) => f.write_fmt(format_args!("{}", s.value)),
+            Step::Subdivision(sd) => f.write_fmt(format_args!("[{}]", Step::join(&sd.steps, " "))),
+            Step::Alternating(a) => f.write_fmt(format_args!("<{}>", Step::join(&a.steps, " "))),
+            Step::Polymeter(pm) => f.write_fmt(format_args!(
+                "{{{}}}%{}",
+                Step::join(&pm.steps, " "),
+                pm.count
+            )),
+            Step::Choices(cs) => f.write_str(&Step::join(&cs.choices, " | ")),
+            Step::Stack(st) => {
+                // stacked channels all are of the same type as their parent group
+                let channels = Step::join_inner(&st.stack, ", ");
+                match st.stack.first() {
+                    Some(Step::Alternating(_)) => f.write_fmt(format_args!("<{}>", channels)),
+                    Some(Step::Polymeter(pm)) => {
+                        f.write_fmt(format_args!("{{{}}}%{}", channels, pm.count))
+                    }
+                    _ => f.write_fmt(format_args!("[{}]", channels)),
+                }
+            }
+            Step::Expression(e) => {
+                f.write_fmt(format_args!("{}{}{}", e.left, e.operator, e.right))
+            }
+            Step::Bjorklund(b) => {
+                f.write_fmt(format_args!("{}({},{}", b.left, b.pulses, b.steps))?;
+                if let Some(rotation) = &b.rotation {
+                    f.write_fmt(format_args!(",{}", rotation))?;
+                }
+                f.write_str(")")
+            }
         }
     }
 }
@@ -112,6 +311,7 @@ impl Single {
             Value::Integer(i) => Some(*i),
             Value::Float(f) => Some(*f as i32),
             Value::Pitch(n) => Some(n.note as i32),
+            Value::Chord(c) => Some(c.pitch.note as i32),
         }
     }
     fn to_target(&self) -> Target {
@@ -122,6 +322,14 @@ impl Single {
             Value::Integer(i) => Target::Index(*i),
             Value::Float(f) => Target::Index(*f as i32),
             Value::Pitch(_n) => Target::Name(self.string.clone()), // TODO might not be the best conversion idea
+            Value::Chord(_c) => Target::Name(self.string.clone()),
+        }
+    }
+    fn to_fraction(&self) -> Option<Fraction> {
+        match &self.value {
+            Value::Integer(i) => Some(Fraction::from(*i)),
+            Value::Float(f) => Some(Fraction::from(*f)),
+            _ => None,
         }
     }
     fn to_chance(&self) -> Option<f64> {
@@ -132,13 +340,13 @@ impl Single {
             Value::Integer(i) => Some((*i as f64).clamp(0.0, 100.0) / 100.0),
             Value::Float(f) => Some(f.clamp(0.0, 1.0)),
             Value::Pitch(n) => Some((n.note as f64).clamp(0.0, 128.0) / 128.0),
+            Value::Chord(c) => Some((c.pitch.note as f64).clamp(0.0, 128.0) / 128.0),
         }
     }
 }
 
 #[derive(Clone, Debug)]
 struct Alternating {
-    current: usize,
     steps: Vec<Step>,
 }
 
@@ -150,7 +358,6 @@ struct Subdivision {
 #[derive(Clone, Debug)]
 struct Polymeter {
     count: usize,
-    offset: usize,
     steps: Vec<Step>,
 }
 
@@ -166,21 +373,39 @@ struct Stack {
 
 #[derive(Clone, Debug)]
 enum Operator {
-    Fast(),    // *
-    Target(),  // :
-    Degrade(), // ?
+    Fast(),      // *
+    Target(),    // :
+    Degrade(),   // ?
     Replicate(), // !
-    // Weight(),     // @
-    // Slow(),       // /
+    Weight(),    // @
+    Slow(),      // /
 }
 impl Operator {
-    fn parse(pair: Pair<Rule>) -> Result<Operator, String> {
+    fn parse(pair: Pair<Rule>) -> Result<Operator, ParseError> {
         match pair.as_rule() {
             Rule::op_fast => Ok(Operator::Fast()),
             Rule::op_target => Ok(Operator::Target()),
             Rule::op_degrade => Ok(Operator::Degrade()),
             Rule::op_replicate => Ok(Operator::Replicate()),
-            _ => Err(format!("unsupported operator: {:?}", pair.as_rule())),
+            Rule::op_weight => Ok(Operator::Weight()),
+            Rule::op_slow => Ok(Operator::Slow()),
+            _ => Err(ParseError::at(
+                &pair,
+                format!("unsupported operator: {:?}", pair.as_rule()),
+            )),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operator::Fast() => f.write_str("*"),
+            Operator::Target() => f.write_str(":"),
+            Operator::Degrade() => f.write_str("?"),
+            Operator::Replicate() => f.write_str("!"),
+            Operator::Weight() => f.write_str("@"),
+            Operator::Slow() => f.write_str("/"),
         }
     }
 }
@@ -201,21 +426,23 @@ struct Bjorklund {
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
-enum Value {
+pub(crate) enum Value {
     #[default]
     Rest,
     Hold,
     Float(f64),
     Integer(i32),
     Pitch(Pitch),
+    Chord(Chord),
     Name(String),
 }
 
 impl Value {
     // parse a single into a value
     // the errors here should be unreachable unless there is a bug in the pest grammar
-    fn parse(pair: Pair<Rule>) -> Result<Value, String> {
+    fn parse(pair: Pair<Rule>) -> Result<Value, ParseError> {
         // println!("{:?}", pair);
+        let position = pair.as_span().start_pos();
         match pair.as_rule() {
             Rule::number => {
                 if let Some(n) = pair.into_inner().next() {
@@ -223,23 +450,46 @@ impl Value {
                         Rule::integer => Ok(Value::Integer(n.as_str().parse::<i32>().unwrap_or(0))),
                         Rule::float => Ok(Value::Float(n.as_str().parse::<f64>().unwrap_or(0.0))),
                         Rule::normal => Ok(Value::Float(n.as_str().parse::<f64>().unwrap_or(0.0))),
-                        _ => Err(format!("unrecognized number\n{:?}", n)),
+                        _ => Err(ParseError::at(&n, "unrecognized number")),
                     }
                 } else {
-                    Err("empty single".to_string())
+                    Err(ParseError::new(position, "empty single"))
                 }
             }
             Rule::hold => Ok(Value::Hold),
             Rule::rest => Ok(Value::Rest),
             Rule::pitch => Ok(Value::Pitch(Pitch::parse(pair))),
+            Rule::chord => Ok(Value::Chord(Chord::parse(pair)?)),
             Rule::name => Ok(Value::Name(pair.as_str().to_string())),
-            _ => Err(format!("unrecognized pair in single\n{:?}", pair)),
+            _ => Err(ParseError::at(&pair, "unrecognized pair in single")),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Rest => f.write_str("~"),
+            Value::Hold => f.write_str("_"),
+            Value::Float(v) => {
+                // print without exponents, but keep the decimal point
+                let string = v.to_string();
+                if string.contains('.') {
+                    f.write_str(&string)
+                } else {
+                    f.write_fmt(format_args!("{}.0", string))
+                }
+            }
+            Value::Integer(i) => f.write_fmt(format_args!("{}", i)),
+            Value::Pitch(p) => f.write_fmt(format_args!("{}", p)),
+            Value::Chord(c) => f.write_fmt(format_args!("{}", c)),
+            Value::Name(n) => f.write_str(n),
         }
     }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
-struct Span {
+pub(crate) struct Span {
     start: Fraction,
     end: Fraction,
 }
@@ -263,7 +513,13 @@ impl Span {
             end: start + outer.length() * self.length(),
         }
     }
-    fn length(&self) -> Fraction {
+    pub(crate) fn start(&self) -> Fraction {
+        self.start
+    }
+    pub(crate) fn end(&self) -> Fraction {
+        self.end
+    }
+    pub(crate) fn length(&self) -> Fraction {
         self.end - self.start
     }
     fn default() -> Self {
@@ -278,7 +534,7 @@ impl Span {
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
-enum Target {
+pub(crate) enum Target {
     #[default]
     None,
     Index(i32),
@@ -300,13 +556,22 @@ impl Display for Target {
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
-struct Event {
+pub(crate) struct Event {
     length: Fraction,
     span: Span,
     value: Value,
     target: Target, // value for instruments
 }
 impl Event {
+    pub(crate) fn span(&self) -> &Span {
+        &self.span
+    }
+    pub(crate) fn value(&self) -> &Value {
+        &self.value
+    }
+    pub(crate) fn target_value(&self) -> &Target {
+        &self.target
+    }
     fn extend(&mut self, next: &Event) {
         self.length += next.length;
         self.span.end = next.span.end
@@ -364,21 +629,21 @@ impl Display for Event {
 }
 
 #[derive(Debug, Clone)]
-struct MultiEvents {
+pub(crate) struct MultiEvents {
     length: Fraction,
     span: Span,
     events: Vec<Events>,
 }
 
 #[derive(Debug, Clone)]
-struct PolyEvents {
+pub(crate) struct PolyEvents {
     length: Fraction,
     span: Span,
     channels: Vec<Events>,
 }
 
 #[derive(Debug, Clone)]
-enum Events {
+pub(crate) enum Events {
     Single(Event),
     Multi(MultiEvents),
     Poly(PolyEvents),
@@ -393,6 +658,13 @@ impl Events {
             target: Target::None,
         })
     }
+    fn set_length(&mut self, length: Fraction) {
+        match self {
+            Events::Single(s) => s.length = length,
+            Events::Multi(m) => m.length = length,
+            Events::Poly(p) => p.length = length,
+        }
+    }
     // only applied for Subdivision and Polymeter groups
     fn subdivide_lengths(events: &mut Vec<Events>) {
         let mut length = Fraction::zero();
@@ -504,7 +776,7 @@ impl Events {
     }
 
     // merge holds then rests separately to avoid collapsing rests and holding notes before them
-    fn merge(channels: &[Vec<Event>]) -> Vec<Vec<Event>> {
+    pub(crate) fn merge(channels: &[Vec<Event>]) -> Vec<Vec<Event>> {
         channels
             .iter()
             .map(Events::merge_holds)
@@ -513,19 +785,20 @@ impl Events {
     }
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[grammar = "rhythm/tidal.pest"]
 pub struct Cycle {
-    root: Step,
-    iteration: u32,
+    source: Source,
+    iteration: usize,
     input: String,
-    rng: Xoshiro256PlusPlus,
+    seed: Option<[u8; 32]>,
+    rng_seed: u64,
 }
 
 impl Cycle {
     // stacks can only appear inside groups like Subdivision, Alternating or Polymeter
     // they will have a stack of steps with their parent's type inside
-    fn parse_stack(pair: Pair<Rule>, parent: Pair<Rule>) -> Result<Step, String> {
+    fn parse_stack(pair: Pair<Rule>, parent: Pair<Rule>) -> Result<Step, ParseError> {
         let mut channels = vec![];
 
         match parent.as_rule() {
@@ -535,7 +808,7 @@ impl Cycle {
                     channels.push(section);
                 }
             }
-            _ => return Err(format!("invalid parent to stack\n{:?}", parent)),
+            _ => return Err(ParseError::at(&parent, "invalid parent to stack")),
         }
 
         let mut stack = Stack { stack: vec![] };
@@ -544,7 +817,6 @@ impl Cycle {
             Rule::alternating => {
                 for c in channels {
                     stack.stack.push(Step::Alternating(Alternating {
-                        current: 0,
                         steps: c,
                     }))
                 }
@@ -560,18 +832,17 @@ impl Cycle {
                 let count = Cycle::parse_polymeter_count(&parent)?;
                 for c in channels {
                     stack.stack.push(Step::Polymeter(Polymeter {
-                        offset: 0,
                         steps: c,
                         count,
                     }))
                 }
             }
-            _ => return Err(format!("invalid parent to stack\n{:?}", parent)),
+            _ => return Err(ParseError::at(&parent, "invalid parent to stack")),
         }
         Ok(Step::Stack(stack))
     }
 
-    fn parse_polymeter_count(pair: &Pair<Rule>) -> Result<usize, String> {
+    fn parse_polymeter_count(pair: &Pair<Rule>) -> Result<usize, ParseError> {
         for p in pair.clone().into_inner() {
             if p.as_rule() == Rule::polymeter_tail {
                 if let Some(count) = p.into_inner().next() {
@@ -580,20 +851,19 @@ impl Cycle {
             }
             // TODO allow more generic parameter here
         }
-        Err(format!("invalid polymeter count\n{:?}", pair))
+        Err(ParseError::at(pair, "invalid polymeter count"))
     }
 
-    fn parse_polymeter(pair: Pair<Rule>) -> Result<Step, String> {
+    fn parse_polymeter(pair: Pair<Rule>) -> Result<Step, ParseError> {
         let count = Cycle::parse_polymeter_count(&pair)?;
         let mut inner = pair.clone().into_inner();
         if let Some(poly_list) = inner.next() {
             return Ok(Step::Polymeter(Polymeter {
                 count,
-                offset: 0,
-                steps: Cycle::parse_section(poly_list).unwrap_or_default(),
+                steps: Cycle::parse_section(poly_list)?,
             }));
         }
-        Err(format!("invalid polymeter\n{:?}", pair))
+        Err(ParseError::at(&pair, "invalid polymeter"))
     }
 
     fn bjorklund_pattern(pulses: i32, steps: i32, rotation: Option<i32>) -> Vec<bool> {
@@ -611,27 +881,86 @@ impl Cycle {
         pattern
     }
 
-    // helper to convert a section rule to a vector of Steps
-    fn parse_section(pair: Pair<Rule>) -> Result<Vec<Step>, String> {
+    // helper to convert a section or split rule to a vector of Steps
+    fn parse_section(pair: Pair<Rule>) -> Result<Vec<Step>, ParseError> {
+        if pair.as_rule() == Rule::split {
+            return Cycle::parse_split(pair);
+        }
         let mut steps = vec![];
         for pair in pair.into_inner() {
-            match Cycle::parse_step(pair) {
-                Ok(s) => steps.push(s),
-                Err(s) => return Err(format!("failed to parse section\n{:?}", s)),
+            if pair.as_rule() == Rule::range {
+                steps.extend(Cycle::parse_range(pair)?);
+                continue;
             }
+            steps.push(Cycle::parse_step(pair)?);
         }
         Ok(steps)
     }
 
+    // helper to convert a split rule to a vector of Subdivisions, one for each section
+    fn parse_split(pair: Pair<Rule>) -> Result<Vec<Step>, ParseError> {
+        let mut steps = vec![];
+        for pair in pair.into_inner() {
+            if pair.as_rule() == Rule::section {
+                let sd = Subdivision {
+                    steps: Cycle::parse_section(pair)?,
+                };
+                steps.push(Step::Subdivision(sd));
+            }
+        }
+        Ok(steps)
+    }
+
+    // helper to expand a range rule to a run of integer Singles
+    fn parse_range(pair: Pair<Rule>) -> Result<Vec<Step>, ParseError> {
+        let mut inner = pair.clone().into_inner();
+        match (inner.next(), inner.next()) {
+            (Some(start), Some(end)) => {
+                let start = start
+                    .as_str()
+                    .parse::<i32>()
+                    .map_err(|err| ParseError::at(&start, err.to_string()))?;
+                let end = end
+                    .as_str()
+                    .parse::<i32>()
+                    .map_err(|err| ParseError::at(&end, err.to_string()))?;
+                // avoid expanding huge ranges into an unreasonable amount of steps
+                const MAX_RANGE_STEPS: u32 = 4096;
+                if start.abs_diff(end) >= MAX_RANGE_STEPS {
+                    return Err(ParseError::at(
+                        &pair,
+                        format!("range is too large: max {} steps", MAX_RANGE_STEPS),
+                    ));
+                }
+                let range: Vec<i32> = if start <= end {
+                    (start..=end).collect()
+                } else {
+                    (end..=start).rev().collect()
+                };
+                Ok(range
+                    .into_iter()
+                    .map(|i| {
+                        Step::Single(Single {
+                            value: Value::Integer(i),
+                            string: i.to_string(),
+                        })
+                    })
+                    .collect())
+            }
+            _ => Err(ParseError::at(&pair, "invalid range")),
+        }
+    }
+
     // helper to convert a section or single to a vector of Steps
-    fn extract_section(pair: Pair<Rule>) -> Result<Vec<Step>, String> {
+    fn extract_section(pair: Pair<Rule>) -> Result<Vec<Step>, ParseError> {
+        let position = pair.as_span().start_pos();
         if let Some(inner) = pair.into_inner().next() {
             match inner.as_rule() {
                 Rule::single => {
                     let single = Step::parse_single(inner)?;
                     Ok(vec![single])
                 }
-                Rule::section => Cycle::parse_section(inner),
+                Rule::section | Rule::split => Cycle::parse_section(inner),
                 Rule::choices => {
                     let mut choices: Vec<Step> = vec![];
                     for p in inner.clone().into_inner() {
@@ -639,30 +968,114 @@ impl Cycle {
                             let choice = Cycle::parse_step(step)?;
                             choices.push(choice)
                         } else {
-                            return Err(format!("empty choice\n{:?}", inner));
+                            return Err(ParseError::at(&inner, "empty choice"));
                         }
                     }
                     Ok(vec![Step::Choices(Choices { choices })])
                 }
-                _ => Err(format!("unexpected rule in section\n{:?}", inner)),
+                _ => Err(ParseError::at(&inner, "unexpected rule in section")),
             }
         } else {
-            Err("empty section".to_string())
+            Err(ParseError::new(position, "empty section"))
+        }
+    }
+
+    // parse an operator parameter as Step, rejecting empty groups
+    fn parse_parameter(pair: Pair<Rule>) -> Result<Step, ParseError> {
+        let is_empty = match pair.as_rule() {
+            Rule::subdivision | Rule::alternating => pair.clone().into_inner().next().is_none(),
+            Rule::polymeter => pair
+                .clone()
+                .into_inner()
+                .next()
+                .filter(|first| first.as_rule() != Rule::polymeter_tail)
+                .is_none(),
+            _ => false,
+        };
+        if is_empty {
+            Err(ParseError::at(&pair, "empty group as operator parameter"))
+        } else {
+            Cycle::parse_step(pair)
+        }
+    }
+
+    // parse an operator pair and apply it on the given left hand side step
+    fn parse_operation(left: Step, op: Pair<Rule>) -> Result<Step, ParseError> {
+        match op.as_rule() {
+            Rule::op_bjorklund => {
+                let mut op_inner = op.clone().into_inner();
+                if let Some(pulse_pair) = op_inner.next() {
+                    let pulses = Cycle::parse_parameter(pulse_pair)?;
+                    if let Some(steps_pair) = op_inner.next() {
+                        let steps = Cycle::parse_parameter(steps_pair)?;
+                        let mut rotate = None;
+                        if let Some(rotate_pair) = op_inner.next() {
+                            rotate = Some(Cycle::parse_parameter(rotate_pair)?);
+                        }
+                        return Ok(Step::Bjorklund(Bjorklund {
+                            left: Box::new(left),
+                            pulses: Box::new(pulses),
+                            steps: Box::new(steps),
+                            rotation: rotate.map(Box::new),
+                        }));
+                    }
+                }
+                Err(ParseError::at(&op, "invalid bjorklund"))
+            }
+            _ => {
+                let operator = Operator::parse(op.clone())?;
+                let position = op.as_span().start_pos();
+                let mut inner = op.into_inner();
+                match inner.next() {
+                    // degrade defaults to a chance of 50%, as in "a?"
+                    None if matches!(operator, Operator::Degrade()) => {
+                        Ok(Step::Expression(Expression {
+                            left: Box::new(left),
+                            right: Box::new(Step::Single(Single {
+                                value: Value::Float(0.5),
+                                string: "0.5".to_string(),
+                            })),
+                            operator,
+                        }))
+                    }
+                    None => Err(ParseError::new(
+                        position,
+                        "missing right hand side in expression",
+                    )),
+                    Some(right_pair) => {
+                        let mut right = Cycle::parse_parameter(right_pair)?;
+                        if matches!(operator, Operator::Target()) {
+                            right.convert_targets_to_names();
+                        }
+                        Ok(Step::Expression(Expression {
+                            left: Box::new(left),
+                            right: Box::new(right),
+                            operator,
+                        }))
+                    }
+                }
+            }
         }
     }
 
     // recursively parse a pair as a Step
     // errors here should be unreachable unless there is a bug in the pest grammar
-    fn parse_step(pair: Pair<Rule>) -> Result<Step, String> {
+    fn parse_step(pair: Pair<Rule>) -> Result<Step, ParseError> {
         match pair.as_rule() {
             Rule::single => Step::parse_single(pair),
+            Rule::target_property => {
+                // note properties are passed as names, they only get applied by the emitters
+                let string = pair.as_str().to_string();
+                let value = Value::Name(string.clone());
+                Ok(Step::Single(Single { value, string }))
+            }
             Rule::subdivision | Rule::mini => {
                 if let Some(first) = pair.clone().into_inner().next() {
                     match first.as_rule() {
                         Rule::stack => Cycle::parse_stack(first, pair),
                         _ => {
                             let sd = Subdivision {
-                                steps: Cycle::extract_section(pair).unwrap_or_default(),
+                                steps: Cycle::extract_section(pair)?,
                             };
                             Ok(Step::Subdivision(sd))
                         }
@@ -677,8 +1090,7 @@ impl Cycle {
                         Rule::stack => Cycle::parse_stack(first, pair),
                         _ => {
                             let a = Alternating {
-                                current: 0,
-                                steps: Cycle::extract_section(pair).unwrap_or_default(),
+                                steps: Cycle::extract_section(pair)?,
                             };
                             Ok(Step::Alternating(a))
                         }
@@ -702,67 +1114,29 @@ impl Cycle {
                 // stacks can only appear inside rules for Subdivision, Alternating or Polymeter
                 // sections and choices are always immediately handled within other rules
                 // using Cycle::extract_section or Cycle::parse_section
-                Err(format!("unexpected pair\n{:?}", pair))
+                Err(ParseError::at(&pair, "unexpected pair"))
             }
             Rule::expr => {
                 let mut inner = pair.clone().into_inner();
                 match inner.next() {
-                    None => Err(format!("empty expression\n{:?}", pair)),
+                    None => Err(ParseError::at(&pair, "empty expression")),
                     Some(left_pair) => {
-                        let left = Cycle::parse_step(left_pair)?;
+                        let mut left = Cycle::parse_step(left_pair)?;
                         match inner.next() {
-                            None => Err(format!("incomplete expression\n{:?}", pair)),
-                            Some(op) => match op.as_rule() {
-                                Rule::op_bjorklund => {
-                                    let mut op_inner = op.into_inner();
-                                    if let Some(pulse_pair) = op_inner.next() {
-                                        let pulses = Cycle::parse_step(pulse_pair)?;
-                                        if let Some(steps_pair) = op_inner.next() {
-                                            let steps = Cycle::parse_step(steps_pair)?;
-                                            let mut rotate = None;
-                                            if let Some(rotate_pair) = op_inner.next() {
-                                                rotate = Some(Cycle::parse_step(rotate_pair)?);
-                                            }
-                                            return Ok(Step::Bjorklund(Bjorklund {
-                                                left: Box::new(left),
-                                                pulses: Box::new(pulses),
-                                                steps: Box::new(steps),
-                                                rotation: rotate.map(Box::new),
-                                            }));
-                                        }
-                                    }
-                                    Err(format!("invalid bjorklund\n{:?}", pair))
-                                }
-                                _ => {
-                                    let operator = Operator::parse(op.clone())?;
-                                    let mut inner = op.into_inner();
-                                    match inner.next() {
-                                        None => Err(format!(
-                                            "missing right hand side in expression\n{:?}",
-                                            inner
-                                        )),
-                                        Some(right_pair) => {
-                                            let right = Cycle::parse_step(right_pair)?;
-                                            match right {
-                                                    Step::Single(_) =>{
-                                                        let expr = Step::Expression(Expression {
-                                                            left: Box::new(left),
-                                                            right: Box::new(right),
-                                                            operator,
-                                                        });
-                                                        Ok(expr)
-                                                    }
-                                                    _ => Err("only single values are supported on the right hand side".to_string())
-                                                }
-                                        }
-                                    }
+                            None => Err(ParseError::at(&pair, "incomplete expression")),
+                            Some(op) => {
+                                // chained operators are applied from left to right
+                                left = Cycle::parse_operation(left, op)?;
+                                for op in inner {
+                                    left = Cycle::parse_operation(left, op)?;
                                 }
-                            },
+                                Ok(left)
+                            }
                         }
                     }
                 }
             }
-            _ => Err(format!("rule not implemented\n{:?}", pair)),
+            _ => Err(ParseError::at(&pair, "rule not implemented")),
         }
     }
 
@@ -811,20 +1185,24 @@ impl Cycle {
         }
     }
 
-    fn reset_step(step: &mut Step, _level: usize) {
-        match step {
-            Step::Alternating(a) => {
-                a.current = 0;
-            }
-            Step::Polymeter(pm) => {
-                pm.offset = 0;
-            }
-            _ => (),
-        }
+    // mix the given value into a random seed, using splitmix64's finalizer
+    fn mix_seed(seed: u64, value: u64) -> u64 {
+        let mut z = seed ^ value.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // create the random number generator for a step with the given seed in the given cycle
+    fn step_rng(seed: u64, cycle: usize) -> Xoshiro256PlusPlus {
+        Xoshiro256PlusPlus::seed_from_u64(Cycle::mix_seed(seed, cycle as u64))
     }
 
-    // recursively output events for the entire cycle based on some state (random seed)
-    fn output(step: &mut Step, rng: &mut Xoshiro256PlusPlus) -> Events {
+    // recursively output events for the given cycle of a step. steps are stateless: their
+    // output only depends on the cycle number and the step's random seed. the seed is derived
+    // from the cycle's seed and the step's position in the tree, so random choices of a step
+    // in a cycle don't depend on any other steps or cycles.
+    fn output(step: &Step, cycle: usize, seed: u64) -> Events {
         match step {
             Step::Single(s) => Events::Single(Event {
                 length: Fraction::one(),
@@ -837,10 +1215,9 @@ impl Cycle {
                     Events::empty()
                 } else {
                     let mut events = vec![];
-                    for s in &mut sd.steps {
-                        let e = Cycle::output(s, rng);
+                    for (i, s) in sd.steps.iter().enumerate() {
+                        let e = Cycle::output(s, cycle, Cycle::mix_seed(seed, i as u64));
                         events.push(e)
-                        // events.extend(output_events(s, rng))
                     }
                     // only applied for Subdivision and Polymeter groups
                     Events::subdivide_lengths(&mut events);
@@ -855,18 +1232,16 @@ impl Cycle {
                 if a.steps.is_empty() {
                     Events::empty()
                 } else {
-                    let current = a.current % a.steps.len();
-                    a.current += 1;
-                    if let Some(step) = a.steps.get_mut(current) {
-                        Cycle::output(step, rng)
-                    } else {
-                        Events::empty() // this can never happen
-                    }
+                    // each step is visited every steps.len() cycles
+                    let length = a.steps.len();
+                    let i = cycle % length;
+                    Cycle::output(&a.steps[i], cycle / length, Cycle::mix_seed(seed, i as u64))
                 }
             }
             Step::Choices(cs) => {
-                let choice = rng.gen_range(0..cs.choices.len());
-                Cycle::output(&mut cs.choices[choice], rng)
+                let choice = Cycle::step_rng(seed, cycle).gen_range(0..cs.choices.len());
+                let choice_seed = Cycle::mix_seed(seed, choice as u64);
+                Cycle::output(&cs.choices[choice], cycle, choice_seed)
             }
             Step::Polymeter(pm) => {
                 if pm.steps.is_empty() {
@@ -874,12 +1249,12 @@ impl Cycle {
                 } else {
                     let mut events = vec![];
                     let length = pm.steps.len();
-                    let offset = pm.offset;
+                    let offset = cycle * pm.count;
 
-                    for i in 0..pm.count {
-                        events.push(Cycle::output(&mut pm.steps[(offset + i) % length], rng))
+                    for i in offset..offset + pm.count {
+                        let step_seed = Cycle::mix_seed(seed, (i % length) as u64);
+                        events.push(Cycle::output(&pm.steps[i % length], i / length, step_seed))
                     }
-                    pm.offset += pm.count;
                     // only applied for Subdivision and Polymeter groups
                     Events::subdivide_lengths(&mut events);
                     Events::Multi(MultiEvents {
@@ -894,8 +1269,8 @@ impl Cycle {
                     Events::empty()
                 } else {
                     let mut channels = vec![];
-                    for s in &mut st.stack {
-                        channels.push(Cycle::output(s, rng))
+                    for (i, s) in st.stack.iter().enumerate() {
+                        channels.push(Cycle::output(s, cycle, Cycle::mix_seed(seed, i as u64)))
                     }
                     Events::Poly(PolyEvents {
                         span: Span::default(),
@@ -905,19 +1280,19 @@ impl Cycle {
                 }
             }
             Step::Expression(e) => {
+                // evaluate the right hand side parameter first, as it may be patterned
+                let right_seed = Cycle::mix_seed(seed, 0);
+                let right = Cycle::output_parameter(e.right.as_ref(), cycle, right_seed);
+                let seed = Cycle::mix_seed(seed, 1);
                 match e.operator {
                     Operator::Fast() => {
                         let mut events = vec![];
-                         #[allow(clippy::single_match)] // TODO support something other than Step::Single as the right hand side
-                        match e.right.as_ref() {
-                            Step::Single(s) => {
-                                if let Some(mult) = s.to_integer() {
-                                    for _i in 0..mult {
-                                        events.push(Cycle::output(&mut e.left, rng))
-                                    }
-                                }
+                        if let Some(mult) = right.to_integer() {
+                            // the left hand side plays mult cycles in a single cycle
+                            let mult = mult.max(0) as usize;
+                            for i in cycle * mult..(cycle + 1) * mult {
+                                events.push(Cycle::output(&e.left, i, seed))
                             }
-                            _ => (), 
                         }
                         Events::subdivide_lengths(&mut events);
                         Events::Multi(MultiEvents {
@@ -927,44 +1302,31 @@ impl Cycle {
                         })
                     }
                     Operator::Target() => {
-                        let mut out = Cycle::output(e.left.as_mut(), rng);
-                         #[allow(clippy::single_match)] // TODO support something other than Step::Single as the right hand side
-                        match e.right.as_ref() {
-                            Step::Single(s) => out.mutate_events(&mut |e| e.target = s.to_target()),
-                            _ => (),
-                        }
+                        let mut out = Cycle::output(&e.left, cycle, seed);
+                        out.mutate_events(&mut |e| e.target = right.to_target());
                         out
                     }
                     Operator::Degrade() => {
-                        let mut out = Cycle::output(e.left.as_mut(), rng);
-                         #[allow(clippy::single_match)] // TODO support something other than Step::Single as the right hand side
-                        match e.right.as_ref() {
-                            Step::Single(s) => out.mutate_events(&mut |e: &mut Event| {
-                                if let Some(chance) = s.to_chance() {
-                                    if chance < rng.gen_range(0.0..1.0) {
-                                        e.value = Value::Rest
-                                    }
+                        let mut out = Cycle::output(&e.left, cycle, seed);
+                        if let Some(chance) = right.to_chance() {
+                            let mut rng = Cycle::step_rng(seed, cycle);
+                            out.mutate_events(&mut |e: &mut Event| {
+                                if chance < rng.gen_range(0.0..1.0) {
+                                    e.value = Value::Rest
                                 }
-                            }),
-                            _ => (),
+                            })
                         }
                         out
                     }
                     Operator::Replicate() => {
                         let mut events = vec![];
                         let mut length = Fraction::from(1);
-                         #[allow(clippy::single_match)] // TODO support something other than Step::Single as the right hand side
-                        match e.right.as_ref() {
-                            Step::Single(s) => {
-                                if let Some(mult) = s.to_integer() {
-                                    length = Fraction::from(mult);
-                                    let out = Cycle::output(&mut e.left, rng);
-                                    for _i in 0..mult {
-                                        events.push(out.clone())
-                                    }
-                                }
+                        if let Some(mult) = right.to_integer() {
+                            length = Fraction::from(mult);
+                            let out = Cycle::output(&e.left, cycle, seed);
+                            for _i in 0..mult {
+                                events.push(out.clone())
                             }
-                            _ => (),
                         }
                         Events::subdivide_lengths(&mut events);
                         Events::Multi(MultiEvents {
@@ -973,41 +1335,47 @@ impl Cycle {
                             events,
                         })
                     }
+                    Operator::Weight() => {
+                        let mut out = Cycle::output(&e.left, cycle, seed);
+                        if let Some(weight) = right.to_fraction() {
+                            if weight > Fraction::zero() {
+                                out.set_length(weight)
+                            }
+                        }
+                        out
+                    }
+                    Operator::Slow() => {
+                        match right.to_fraction().filter(|f| *f > Fraction::zero()) {
+                            Some(factor) => Cycle::output_slow(&e.left, factor, cycle, seed),
+                            None => Cycle::output(&e.left, cycle, seed),
+                        }
+                    }
                 }
             }
             Step::Bjorklund(b) => {
                 let mut events = vec![];
-                #[allow(clippy::single_match)] // TODO support something other than Step::Single as the right hand side
-                match b.pulses.as_ref() {
-                    Step::Single(pulses_single) => {
-                        match b.steps.as_ref() {
-                            Step::Single(steps_single) => {
-                                let rotation = match &b.rotation {
-                                    Some(r) => match r.as_ref() {
-                                        Step::Single(rotation_single) => rotation_single.to_integer(),
-                                        _ => None, // TODO support something other than Step::Single as rotation
-                                    },
-                                    None => None,
-                                };
-                                if let Some(pulses) = pulses_single.to_integer() {
-                                    if let Some(steps) = steps_single.to_integer() {
-                                        let out = Cycle::output(&mut b.left, rng);
-                                        for pulse in
-                                            Cycle::bjorklund_pattern(pulses, steps, rotation)
-                                        {
-                                            if pulse {
-                                                events.push(out.clone())
-                                            } else {
-                                                events.push(Events::empty())
-                                            }
-                                        }
-                                    }
-                                }
+                let pulses_seed = Cycle::mix_seed(seed, 0);
+                let pulses = Cycle::output_parameter(&b.pulses, cycle, pulses_seed).to_integer();
+                let steps_seed = Cycle::mix_seed(seed, 1);
+                let steps = Cycle::output_parameter(&b.steps, cycle, steps_seed).to_integer();
+                let rotation = match &b.rotation {
+                    Some(r) => {
+                        let rotation_seed = Cycle::mix_seed(seed, 2);
+                        Cycle::output_parameter(r, cycle, rotation_seed).to_integer()
+                    }
+                    None => None,
+                };
+                if let Some(pulses) = pulses {
+                    if let Some(steps) = steps {
+                        let out = Cycle::output(&b.left, cycle, Cycle::mix_seed(seed, 3));
+                        for pulse in Cycle::bjorklund_pattern(pulses, steps, rotation) {
+                            if pulse {
+                                events.push(out.clone())
+                            } else {
+                                events.push(Events::empty())
                             }
-                            _ => (), // TODO support something other than Step::Single as steps
                         }
                     }
-                    _ => (), // TODO support something other than Step::Single as pulses
                 }
                 Events::subdivide_lengths(&mut events);
                 Events::Multi(MultiEvents {
@@ -1019,6 +1387,89 @@ impl Cycle {
         }
     }
 
+    // output the given cycle of a step which is used as operator parameter.
+    // patterned parameters get resolved to the first value they output in the cycle.
+    fn output_parameter(step: &Step, cycle: usize, seed: u64) -> Single {
+        match step {
+            Step::Single(s) => s.clone(),
+            _ => {
+                let mut events = Cycle::output(step, cycle, seed);
+                Cycle::transform_spans(&mut events, &Span::default());
+                let mut channels = vec![];
+                events.flatten(&mut channels, 0);
+                match channels.first().and_then(|channel| channel.first()) {
+                    Some(event) => Single {
+                        string: event.value.to_string(),
+                        value: event.value.clone(),
+                    },
+                    None => Single::default(),
+                }
+            }
+        }
+    }
+
+    // output the given cycle of a step which is slowed down by the given factor:
+    // cycles of the slowed down step are spread over multiple output cycles.
+    // events only get emitted in the output cycle they start in, but keep their entire length.
+    // factors < 1 speed up the step: they get clamped to limit the number of cycles per output.
+    fn output_slow(step: &Step, factor: Fraction, cycle: usize, seed: u64) -> Events {
+        const MAX_SLOW_CYCLES: u64 = 1024;
+        let factor = factor.max(Fraction::new(1u64, MAX_SLOW_CYCLES));
+        let start = Fraction::from(cycle as u64);
+        let end = start + Fraction::one();
+        // output all cycles of the slowed down step which overlap the given cycle
+        let first = (start / factor).floor().to_usize().unwrap_or(0);
+        let last = (end / factor).ceil().to_usize().unwrap_or(first + 1);
+        let mut slow_channels: Vec<Vec<Event>> = vec![];
+        for slow_cycle in first..last {
+            let slow_start = factor * Fraction::from(slow_cycle as u64);
+            let mut events = Cycle::output(step, slow_cycle, seed);
+            Cycle::transform_spans(&mut events, &Span::new(slow_start, slow_start + factor));
+            let mut channels = vec![];
+            events.flatten(&mut channels, 0);
+            for (index, channel) in channels.into_iter().enumerate() {
+                if slow_channels.len() <= index {
+                    slow_channels.push(vec![])
+                }
+                slow_channels[index].extend(channel)
+            }
+        }
+        // move events which start in the given cycle into the cycle's relative time
+        let mut channels = vec![];
+        for channel in &slow_channels {
+            let mut events = vec![];
+            for event in channel
+                .iter()
+                .filter(|event| event.span.start >= start && event.span.start < end)
+            {
+                let span = Span::new(event.span.start - start, event.span.end - start);
+                events.push(Events::Single(Event {
+                    length: span.length(),
+                    span,
+                    value: event.value.clone(),
+                    target: event.target.clone(),
+                }));
+            }
+            if events.is_empty() {
+                events.push(Events::empty());
+            }
+            channels.push(Events::Multi(MultiEvents {
+                span: Span::default(),
+                length: Fraction::one(),
+                events,
+            }));
+        }
+        if channels.len() == 1 {
+            channels.remove(0)
+        } else {
+            Events::Poly(PolyEvents {
+                span: Span::default(),
+                length: Fraction::one(),
+                channels,
+            })
+        }
+    }
+
     // recursively transform the spans of events from relative time to absolute
     fn transform_spans(events: &mut Events, span: &Span) {
         let unit = span.length();
@@ -1045,64 +1496,92 @@ impl Cycle {
         }
     }
 
-    // reset all the stateful steps like Alternating and Polymeter to their starting state
-    fn reset(&mut self) {
-        self.iteration = 0;
-        Cycle::crawl(&mut self.root, Cycle::reset_step, 0)
-    }
-
-    // parse the root pair of the pest AST into a Subdivision
-    // then update the spans of all the generated steps
-    fn generate(&mut self) -> Vec<Vec<Event>> {
-        let mut events = Cycle::output(&mut self.root, &mut self.rng);
+    // output the events of the given cycle of a step with spans relative to the cycle's start
+    fn output_step(&self, step: &Step, cycle: usize) -> Vec<Vec<Event>> {
+        let mut events = Cycle::output(step, cycle, self.rng_seed);
         Cycle::transform_spans(&mut events, &Span::default());
         let mut channels = vec![];
         events.flatten(&mut channels, 0);
+        channels
+    }
 
-        // this is just for test debugging
-        println!("\nOUTPUT {}", self.iteration);
-        let channel_count = channels.len();
-        for (ci, channel) in channels.iter().enumerate() {
-            if channel_count > 1 {
-                println!(" /{}", ci);
-            }
-            for (i, event) in channel.iter().enumerate() {
-                println!("  │{:02}│ {}", i, event);
-            }
+    // output the events of the given cycle with spans relative to the cycle's start
+    fn output_cycle(&self, cycle: usize) -> Vec<Vec<Event>> {
+        match &self.source {
+            Source::Mini(root) => self.output_step(root, cycle),
+            source => self.output_source(source, cycle),
+        }
+    }
+
+    // rewind to the first cycle and pick a new random seed when the cycle is not seeded
+    pub(crate) fn reset(&mut self) {
+        self.iteration = 0;
+        if self.seed.is_none() {
+            self.rng_seed = thread_rng().gen();
         }
+    }
 
+    // output the events of the next cycle, with spans relative to the cycle's start
+    pub(crate) fn generate(&mut self) -> Vec<Vec<Event>> {
+        let channels = self.output_cycle(self.iteration);
         self.iteration += 1;
         channels
     }
 
+    // output all events with spans that intersect the given window in absolute cycle time,
+    // e.g. 0 to 1/2 is the first half of the first cycle. this does not change the state of
+    // the cycle, so any window can be queried at any time.
+    pub(crate) fn query(&self, start: Fraction, end: Fraction) -> Vec<Vec<Event>> {
+        let mut channels: Vec<Vec<Event>> = vec![];
+        if end <= start || end <= Fraction::zero() {
+            return channels;
+        }
+        let first = start.floor().to_usize().unwrap_or(0);
+        let last = end.ceil().to_usize().unwrap_or(first);
+        for cycle in first..last {
+            let offset = Fraction::from(cycle as u64);
+            for (index, channel) in self.output_cycle(cycle).into_iter().enumerate() {
+                if channels.len() <= index {
+                    channels.push(vec![])
+                }
+                for mut event in channel {
+                    event.span = Span::new(event.span.start + offset, event.span.end + offset);
+                    if event.span.start < end && event.span.end > start {
+                        channels[index].push(event);
+                    }
+                }
+            }
+        }
+        channels
+    }
+
     fn is_stateful(&self) -> bool {
         ['<', '{', '|', '?'].iter().any(|&c| self.input.contains(c))
     }
 
-    fn from(input: &str, seed: Option<[u8; 32]>) -> Result<Self, String> {
+    pub(crate) fn from(input: &str, seed: Option<[u8; 32]>) -> Result<Self, ParseError> {
         match Cycle::parse(Rule::mini, input) {
             Ok(mut tree) => {
                 if let Some(mini) = tree.next() {
-                    println!("\nTREE");
-                    Cycle::print_pairs(&mini, 0);
-                    let root = Cycle::parse_step(mini)?;
-                    let seed = seed.unwrap_or_else(|| thread_rng().gen());
-                    let rng = Xoshiro256PlusPlus::from_seed(seed);
+                    let source = Source::Mini(Cycle::parse_step(mini)?);
+                    let rng_seed = match seed {
+                        Some(seed) => Xoshiro256PlusPlus::from_seed(seed).gen(),
+                        None => thread_rng().gen(),
+                    };
                     let iteration = 0;
-                    let mut cycle = Self {
-                        root,
-                        rng,
+                    let cycle = Self {
+                        source,
+                        rng_seed,
+                        seed,
                         iteration,
                         input: input.to_string(),
                     };
-                    println!("\nCYCLE");
-                    cycle.print();
                     Ok(cycle)
                 } else {
-                    Err("couldn't parse input".to_string())
+                    Err(ParseError::from_message(input, "couldn't parse input"))
                 }
             }
-            Err(err) => Err(format!("{}", err)),
+            Err(err) => Err(ParseError::from_pest(err)),
         }
     }
 
@@ -1117,7 +1596,7 @@ impl Cycle {
     fn print_steps(step: &mut Step, level: usize) {
         let name = match step {
             Step::Single(s) => match &s.value {
-                Value::Pitch(_p) => format!("{:?} {}", s.value, s.string),
+                Value::Pitch(_) | Value::Chord(_) => format!("{:?} {}", s.value, s.string),
                 _ => format!("{:?} {:?}", s.value, s.string),
             },
             Step::Subdivision(sd) => format!("{} [{}]", "Subdivision", sd.steps.len()),
@@ -1143,9 +1622,23 @@ impl Cycle {
         }
     }
 
-    // TODO impl Display for Cycle
     fn print(&mut self) {
-        Cycle::crawl(&mut self.root, Cycle::print_steps, 0);
+        Cycle::crawl(self.source.root_mut(), Cycle::print_steps, 0);
+    }
+
+    // normalized mini-notation of the parsed cycle, see `impl Display for Cycle`
+    pub(crate) fn to_mini_notation(&self) -> String {
+        self.to_string()
+    }
+}
+
+// prints the parsed cycle as normalized mini-notation: parsing the printed string results in a
+// cycle with the same output, unless transformations got applied. transformations are not part
+// of the mini-notation, so only the parsed, untransformed cycle is printed. the root subdivision
+// or stack is printed without its brackets.
+impl Display for Cycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source.root().inner_notation())
     }
 }
 
@@ -1487,6 +1980,173 @@ mod test {
             ],
         )?;
 
+        assert_eq!(
+            Cycle::from("c4@3 e4", None)?.generate(),
+            [[
+                Event::at(F::from(0), F::new(3u8, 4u8)).note(0, 4),
+                Event::at(F::new(3u8, 4u8), F::new(1u8, 4u8)).note(4, 4),
+            ]]
+        );
+
+        assert_eq!(
+            Cycle::from("[a b]@1.5 c", None)?.generate(),
+            [[
+                Event::at(F::from(0), F::new(3u8, 10u8)).note(9, 4),
+                Event::at(F::new(3u8, 10u8), F::new(3u8, 10u8)).note(11, 4),
+                Event::at(F::new(3u8, 5u8), F::new(2u8, 5u8)).note(0, 4),
+            ]]
+        );
+
+        assert_cycles(
+            "[c4 e4 g4]/2",
+            vec![
+                vec![vec![
+                    Event::at(F::from(0), F::new(2u8, 3u8)).note(0, 4),
+                    Event::at(F::new(2u8, 3u8), F::new(2u8, 3u8)).note(4, 4),
+                ]],
+                vec![vec![
+                    Event::at(F::new(1u8, 3u8), F::new(2u8, 3u8)).note(7, 4)
+                ]],
+                vec![vec![
+                    Event::at(F::from(0), F::new(2u8, 3u8)).note(0, 4),
+                    Event::at(F::new(2u8, 3u8), F::new(2u8, 3u8)).note(4, 4),
+                ]],
+            ],
+        )?;
+
+        assert_cycles(
+            "a <b c>/2",
+            vec![
+                vec![vec![
+                    Event::at(F::from(0), F::new(1u8, 2u8)).note(9, 4),
+                    Event::at(F::new(1u8, 2u8), F::from(1)).note(11, 4),
+                ]],
+                vec![vec![
+                    Event::at(F::from(0), F::new(1u8, 2u8)).note(9, 4),
+                    Event::at(F::new(1u8, 2u8), F::new(1u8, 2u8)),
+                ]],
+                vec![vec![
+                    Event::at(F::from(0), F::new(1u8, 2u8)).note(9, 4),
+                    Event::at(F::new(1u8, 2u8), F::from(1)).note(0, 4),
+                ]],
+            ],
+        )?;
+
+        // slowed down events keep their entire length
+        assert_cycles(
+            "c4/2",
+            vec![
+                vec![vec![Event::at(F::from(0), F::from(2)).note(0, 4)]],
+                vec![vec![Event::at(F::from(0), F::from(1))]],
+            ],
+        )?;
+
+        // slow factors < 1 get clamped
+        assert_eq!(Cycle::from("a/0.00001", None)?.generate()[0].len(), 1024);
+
+        assert_cycles(
+            "c4*<2 3>",
+            vec![
+                vec![vec![
+                    Event::at(F::from(0), F::new(1u8, 2u8)).note(0, 4),
+                    Event::at(F::new(1u8, 2u8), F::new(1u8, 2u8)).note(0, 4),
+                ]],
+                vec![vec![
+                    Event::at(F::from(0), F::new(1u8, 3u8)).note(0, 4),
+                    Event::at(F::new(1u8, 3u8), F::new(1u8, 3u8)).note(0, 4),
+                    Event::at(F::new(2u8, 3u8), F::new(1u8, 3u8)).note(0, 4),
+                ]],
+            ],
+        )?;
+
+        assert_cycles(
+            "bd(<3 5>,8,<0 [1 2]>)",
+            vec![
+                vec![vec![
+                    Event::at(F::from(0), F::new(1u8, 8u8)).name("bd"),
+                    Event::at(F::new(1u8, 8u8), F::new(1u8, 8u8)),
+                    Event::at(F::new(2u8, 8u8), F::new(1u8, 8u8)),
+                    Event::at(F::new(3u8, 8u8), F::new(1u8, 8u8)).name("bd"),
+                    Event::at(F::new(4u8, 8u8), F::new(1u8, 8u8)),
+                    Event::at(F::new(5u8, 8u8), F::new(1u8, 8u8)),
+                    Event::at(F::new(6u8, 8u8), F::new(1u8, 8u8)).name("bd"),
+                    Event::at(F::new(7u8, 8u8), F::new(1u8, 8u8)),
+                ]],
+                vec![vec![
+                    Event::at(F::from(0), F::new(1u8, 8u8)),
+                    Event::at(F::new(1u8, 8u8), F::new(1u8, 8u8)).name("bd"),
+                    Event::at(F::new(2u8, 8u8), F::new(1u8, 8u8)),
+                    Event::at(F::new(3u8, 8u8), F::new(1u8, 8u8)).name("bd"),
+                    Event::at(F::new(4u8, 8u8), F::new(1u8, 8u8)).name("bd"),
+                    Event::at(F::new(5u8, 8u8), F::new(1u8, 8u8)),
+                    Event::at(F::new(6u8, 8u8), F::new(1u8, 8u8)).name("bd"),
+                    Event::at(F::new(7u8, 8u8), F::new(1u8, 8u8)).name("bd"),
+                ]],
+            ],
+        )?;
+
+        assert_cycles(
+            "a!< 2 [1 3] >:<x y>",
+            vec![
+                vec![vec![
+                    Event::at(F::from(0), F::new(1u8, 2u8))
+                        .note(9, 4)
+                        .target(Target::Name("x".to_string())),
+                    Event::at(F::new(1u8, 2u8), F::new(1u8, 2u8))
+                        .note(9, 4)
+                        .target(Target::Name("x".to_string())),
+                ]],
+                vec![vec![Event::at(F::from(0), F::from(1))
+                    .note(9, 4)
+                    .target(Target::Name("y".to_string()))]],
+            ],
+        )?;
+
+        assert_eq!(
+            Cycle::from("0 .. 3 -1..-2", None)?.generate(),
+            [[
+                Event::at(F::from(0), F::new(1u8, 6u8)).int(0),
+                Event::at(F::new(1u8, 6u8), F::new(1u8, 6u8)).int(1),
+                Event::at(F::new(2u8, 6u8), F::new(1u8, 6u8)).int(2),
+                Event::at(F::new(3u8, 6u8), F::new(1u8, 6u8)).int(3),
+                Event::at(F::new(4u8, 6u8), F::new(1u8, 6u8)).int(-1),
+                Event::at(F::new(5u8, 6u8), F::new(1u8, 6u8)).int(-2),
+            ]]
+        );
+
+        assert_eq!(
+            Cycle::from("a b . c d e", None)?.generate(),
+            Cycle::from("[a b] [c d e]", None)?.generate(),
+        );
+
+        assert_eq!(
+            Cycle::from("<a . b c>, [1 . 2 .. 4]", None)?.generate(),
+            Cycle::from("<[a] [b c]>, [[1] [2 3 4]]", None)?.generate(),
+        );
+
+        let cycle = Cycle::from("a <b c> [d e]/2", None)?;
+        assert_eq!(
+            cycle.query(F::new(1u8, 2u8), F::new(3u8, 2u8)),
+            [[
+                Event::at(F::new(1u8, 3u8), F::new(1u8, 3u8)).note(11, 4),
+                Event::at(F::new(2u8, 3u8), F::new(1u8, 3u8)).note(2, 4),
+                Event::at(F::from(1), F::new(1u8, 3u8)).note(9, 4),
+                Event::at(F::new(4u8, 3u8), F::new(1u8, 3u8)).note(0, 4),
+            ]]
+        );
+        assert_eq!(
+            cycle.query(F::from(5), F::new(16u8, 3u8)),
+            [[Event::at(F::from(5), F::new(1u8, 3u8)).note(9, 4)]]
+        );
+        assert_eq!(
+            cycle.query(F::from(5), F::new(17u8, 3u8)),
+            [[
+                Event::at(F::from(5), F::new(1u8, 3u8)).note(9, 4),
+                Event::at(F::new(16u8, 3u8), F::new(1u8, 3u8)).note(0, 4),
+            ]]
+        );
+        assert!(cycle.query(F::from(1), F::from(1)).is_empty());
+
         // TODO test random outputs // parse_with_debug("[a b c d]?0.5");
 
         assert!(Cycle::from("a b c [d", None).is_err());
@@ -1502,7 +2162,119 @@ mod test {
         assert!(Cycle::from("#c $", None).is_err());
         assert!(Cycle::from("1.. 2 3", None).is_err());
         assert!(Cycle::from("1 ..2 3", None).is_err());
+        assert!(Cycle::from("a .b", None).is_err());
+        assert!(Cycle::from("a . . b", None).is_err());
+        assert!(Cycle::from("0 .. a", None).is_err());
+        assert!(Cycle::from("0 .. 2000000000", None).is_err());
 
         Ok(())
     }
+
+    #[test]
+    pub fn test_mini_notation() -> Result<(), String> {
+        // parse -> print -> parse must give the same output
+        fn assert_round_trip(input: &str, expected: &str) -> Result<(), String> {
+            let seed = Some([7u8; 32]);
+            let cycle = Cycle::from(input, seed)?;
+            let printed = cycle.to_mini_notation();
+            assert_eq!(printed, expected);
+            let reparsed = Cycle::from(&printed, seed)?;
+            assert_eq!(reparsed.to_mini_notation(), printed);
+            for index in 0..8 {
+                assert_eq!(cycle.output_cycle(index), reparsed.output_cycle(index));
+            }
+            Ok(())
+        }
+
+        assert_round_trip("a  b   C4 ~ _", "a4 b4 c4 ~ _")?;
+        assert_round_trip("1 -2 1.0 .5 bd", "1 -2 1.0 0.5 bd")?;
+        assert_round_trip("c'maj e4'min7'ii g'maj'5", "c4'maj e4'min7'ii g4'maj'5")?;
+        assert_round_trip("[a b] <c d> {e f g}%4", "[a4 b4] <c4 d4> {e4 f4 g4}%4")?;
+        assert_round_trip("a b, c d", "a4 b4, c4 d4")?;
+        assert_round_trip("[a b, c] <a, b c>", "[a4 b4, c4] <a4, b4 c4>")?;
+        assert_round_trip("{a b, c d e}%4", "{a4 b4, c4 d4 e4}%4")?;
+        assert_round_trip("a | b | [c d]", "a4 | b4 | [c4 d4]")?;
+        assert_round_trip("[a | b] c", "[a4 | b4] c4")?;
+        assert_round_trip("a*2:3 b?0.5 c!3", "a4*2:3 b4?0.5 c4!3")?;
+        assert_round_trip("a? b", "a4?0.5 b4")?;
+        assert_round_trip("a@3 b/2 c*<2 3>", "a4@3 b4/2 c4*<2 3>")?;
+        assert_round_trip("a:v0.5 b:p-1 c:#3 bd:d.5", "a4:v0.5 b4:p-1 c4:#3 bd:d.5")?;
+        assert_round_trip("a(3,8) b(<3 5>,8,2)*2", "a4(3,8) b4(<3 5>,8,2)*2")?;
+        assert_round_trip("0 .. 3", "0 1 2 3")?;
+        assert_round_trip("a b . c d e", "[a4 b4] [c4 d4 e4]")?;
+        assert_round_trip(
+            "1.5 -0.25 12345678901234567890.0",
+            "1.5 -0.25 12345678901234567000.0",
+        )?;
+        assert_round_trip("0.000001 100000000.", "0.000001 100000000.0")?;
+        assert_round_trip("bd:a sn:C4 hh:<e4'maj b>", "bd:a sn:C4 hh:<e4'maj b>")?;
+
+        // transformations are not printed: the printed cycle is the untransformed one
+        let seed = Some([7u8; 32]);
+        let cycle = Cycle::from("a <b c>", seed)?.fast(Fraction::from(2)).rev();
+        assert_eq!(cycle.to_mini_notation(), "a4 <b4 c4>");
+        let mut reparsed = Cycle::from(&cycle.to_mini_notation(), seed)?;
+        let mut untransformed = Cycle::from("a <b c>", seed)?;
+        for _ in 0..4 {
+            assert_eq!(reparsed.generate(), untransformed.generate());
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_parse_errors() {
+        let error = Cycle::from("a b [c d", None).unwrap_err();
+        assert_eq!((error.offset(), error.line(), error.column()), (8, 1, 9));
+        assert!(error.expected().contains(&"'['".to_string()));
+        assert!(!error.expected().contains(&"an octave".to_string()));
+        assert!(error.message().starts_with("expected "));
+        assert_eq!(error.excerpt(), "  |\n1 | a b [c d\n  |         ^");
+
+        let error = Cycle::from("a b*", None).unwrap_err();
+        assert_eq!((error.offset(), error.column()), (4, 5));
+        assert!(error.to_string().starts_with("expected "));
+        assert!(error.to_string().contains("at line 1, column 5"));
+
+        // semantic errors are located too
+        let error = Cycle::from("c4'maj e4'qwe", None).unwrap_err();
+        assert_eq!((error.offset(), error.line(), error.column()), (10, 1, 11));
+        assert!(error.message().starts_with("unknown chord mode 'qwe'"));
+        assert!(error.expected().is_empty());
+    }
+
+    #[test]
+    pub fn test_random() -> Result<(), String> {
+        // random choices only depend on the seed, cycle and step position
+        fn assert_deterministic(input: &str) -> Result<(), String> {
+            let seed = Some([3u8; 32]);
+            let mut cycle = Cycle::from(input, seed)?;
+            let played = (0..32).map(|_| cycle.generate()).collect::<Vec<_>>();
+            // regenerating cycles in isolation and in any order matches the played cycles
+            let other = Cycle::from(input, seed)?;
+            for index in (0..32).rev() {
+                assert_eq!(other.output_cycle(index), played[index]);
+            }
+            // resetting a seeded cycle replays the same cycles
+            cycle.reset();
+            assert_eq!(cycle.generate(), played[0]);
+            // choices vary over cycles
+            assert!(played.iter().any(|channels| *channels != played[0]));
+            Ok(())
+        }
+
+        assert_deterministic("a | b | c")?;
+        assert_deterministic("[a | b | c] [d | e]")?;
+        assert_deterministic("a? b?0.3 [c d e f]?")?;
+        assert_deterministic("<a b> [c | d]*2 e(3,8,<0 2>)?")?;
+        assert_deterministic("[a | b]/3 c?")?;
+
+        // changing steps doesn't change the choices of other steps in a stack
+        let seed = Some([5u8; 32]);
+        let a = Cycle::from("[a | b | c], d", seed)?;
+        let b = Cycle::from("[a | b | c], d? e", seed)?;
+        for index in 0..16 {
+            assert_eq!(a.output_cycle(index)[0], b.output_cycle(index)[0]);
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,212 @@
+use std::fmt::Display;
+
+use pest::{
+    error::{Error, ErrorVariant, InputLocation, LineColLocation},
+    iterators::Pair,
+    Position,
+};
+
+use super::Rule;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Error of a failed mini-notation parse, with the location of the error in the parsed string.
+///
+/// Displays as a message with the error's line and column, followed by an excerpt of the
+/// failed line which marks the error's location with a caret.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    offset: usize,
+    line: usize,
+    column: usize,
+    expected: Vec<String>,
+    excerpt: String,
+}
+
+impl ParseError {
+    // create a new error at the given position in the parsed string
+    pub(super) fn new<S: Into<String>>(position: Position, message: S) -> Self {
+        let (line, column) = position.line_col();
+        Self::from_parts(
+            message.into(),
+            position.pos(),
+            line,
+            column,
+            position.line_of(),
+            vec![],
+        )
+    }
+
+    // create a new error at the start of the given pair
+    pub(super) fn at<S: Into<String>>(pair: &Pair<Rule>, message: S) -> Self {
+        Self::new(pair.as_span().start_pos(), message)
+    }
+
+    // create a new error at the start of the given input string
+    pub(super) fn from_message<S: Into<String>>(input: &str, message: S) -> Self {
+        let line = input.lines().next().unwrap_or_default();
+        Self::from_parts(message.into(), 0, 1, 1, line, vec![])
+    }
+
+    // convert a pest parser error, describing expected rules in human terms
+    pub(super) fn from_pest(error: Error<Rule>) -> Self {
+        let offset = match error.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        };
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(pos) => pos,
+            LineColLocation::Span(start, _) => start,
+        };
+        let (message, expected) = match &error.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => {
+                let mut expected: Vec<String> = vec![];
+                // skip rules which only continue the preceding token, such as octaves of pitches
+                for rule in positives.iter().filter(|rule| !Self::is_continuation(rule)) {
+                    let description = Self::describe_rule(rule).to_string();
+                    if !expected.contains(&description) {
+                        expected.push(description);
+                    }
+                }
+                let message = if !expected.is_empty() {
+                    format!("expected {}", Self::enumerate(&expected))
+                } else if let Some(rule) = negatives.first() {
+                    format!("unexpected {}", Self::describe_rule(rule))
+                } else {
+                    "unexpected input".to_string()
+                };
+                (message, expected)
+            }
+            ErrorVariant::CustomError { message } => (message.clone(), vec![]),
+        };
+        Self::from_parts(message, offset, line, column, error.line(), expected)
+    }
+
+    /// Human readable description of the error, without location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Byte offset of the error in the parsed string.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Line number of the error in the parsed string, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Column of the error in the error's line, in characters, starting from 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Human readable descriptions of the tokens that were expected at the error's location,
+    /// if any, e.g. `"']'"` or `"a number"`.
+    pub fn expected(&self) -> &[String] {
+        &self.expected
+    }
+
+    /// Excerpt of the failed line, marking the error's location with a caret.
+    pub fn excerpt(&self) -> &str {
+        &self.excerpt
+    }
+
+    fn from_parts(
+        message: String,
+        offset: usize,
+        line: usize,
+        column: usize,
+        line_str: &str,
+        expected: Vec<String>,
+    ) -> Self {
+        let line_str = line_str.trim_end_matches(['\r', '\n']);
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+        let caret = " ".repeat(column.saturating_sub(1));
+        let excerpt = format!("{gutter} |\n{number} | {line_str}\n{gutter} | {caret}^");
+        Self {
+            message,
+            offset,
+            line,
+            column,
+            expected,
+            excerpt,
+        }
+    }
+
+    // join descriptions as "a", "a or b", "a, b or c"
+    fn enumerate(descriptions: &[String]) -> String {
+        match descriptions.split_last() {
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+            None => String::new(),
+        }
+    }
+
+    fn is_continuation(rule: &Rule) -> bool {
+        matches!(rule, Rule::digit | Rule::octave | Rule::mark)
+    }
+
+    fn describe_rule(rule: &Rule) -> &'static str {
+        match rule {
+            Rule::EOI => "end of input",
+            Rule::WHITESPACE => "whitespace",
+            Rule::digit | Rule::integer | Rule::normal | Rule::float | Rule::number => "a number",
+            Rule::octave => "an octave",
+            Rule::mark => "'#' or 'b'",
+            Rule::note | Rule::pitch => "a pitch",
+            Rule::chord => "a chord",
+            Rule::chord_mode => "a chord mode",
+            Rule::chord_inversion => "a chord inversion",
+            Rule::chord_voicing => "a chord voicing",
+            Rule::rest => "'~'",
+            Rule::hold => "'_'",
+            Rule::name => "a name",
+            Rule::single => "a value",
+            Rule::target_property => "a target property",
+            Rule::op_fast => "'*'",
+            Rule::op_target => "':'",
+            Rule::op_degrade => "'?'",
+            Rule::op_replicate => "'!'",
+            Rule::op_bjorklund => "'('",
+            Rule::op_weight => "'@'",
+            Rule::op_slow => "'/'",
+            Rule::op => "an operator",
+            Rule::subdivision => "'['",
+            Rule::alternating => "'<'",
+            Rule::polymeter => "'{'",
+            Rule::polymeter_tail => "'%'",
+            Rule::polymeter_count => "a polymeter count",
+            Rule::group => "a group",
+            Rule::parameter => "a parameter",
+            Rule::expr => "an expression",
+            Rule::range => "a range",
+            Rule::split_dot => "'.'",
+            Rule::section | Rule::choice | Rule::choices | Rule::split | Rule::stack => "a step",
+            Rule::mini => "a mini-notation",
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{} at line {}, column {}\n{}",
+            self.message, self.line, self.column, self.excerpt
+        ))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> Self {
+        error.to_string()
+    }
+}
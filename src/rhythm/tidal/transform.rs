@@ -0,0 +1,431 @@
+//! Tidal pattern transformations, such as `fast`, `rev` or `every`, applied on parsed cycles.
+
+use fraction::{Fraction, One, ToPrimitive, Zero};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::{Cycle, Event, Events, Span, Step};
+
+// -------------------------------------------------------------------------------------------------
+
+// stateless source of cycle events: a parsed mini-notation or a transformation of other sources
+#[derive(Clone, Debug)]
+pub(crate) enum Source {
+    Mini(Step),
+    Fast(Box<Source>, Fraction),
+    Shift(Box<Source>, Fraction),
+    Rev(Box<Source>),
+    Stack(Vec<Source>),
+    Every(Box<Source>, usize, Box<Source>),
+    DegradeBy(Box<Source>, f64),
+    Palindrome(Box<Source>),
+    Iter(Box<Source>, usize),
+    Chunk(Box<Source>, usize, Box<Source>),
+}
+
+impl Source {
+    // the first parsed mini-notation step in the source
+    pub(super) fn root(&self) -> &Step {
+        match self {
+            Source::Mini(step) => step,
+            Source::Stack(sources) => sources
+                .first()
+                .map(Source::root)
+                .expect("stacks can not be empty"),
+            Source::Fast(source, _)
+            | Source::Shift(source, _)
+            | Source::Rev(source)
+            | Source::Every(source, _, _)
+            | Source::DegradeBy(source, _)
+            | Source::Palindrome(source)
+            | Source::Iter(source, _)
+            | Source::Chunk(source, _, _) => source.root(),
+        }
+    }
+
+    pub(super) fn root_mut(&mut self) -> &mut Step {
+        match self {
+            Source::Mini(step) => step,
+            Source::Stack(sources) => sources
+                .first_mut()
+                .map(Source::root_mut)
+                .expect("stacks can not be empty"),
+            Source::Fast(source, _)
+            | Source::Shift(source, _)
+            | Source::Rev(source)
+            | Source::Every(source, _, _)
+            | Source::DegradeBy(source, _)
+            | Source::Palindrome(source)
+            | Source::Iter(source, _)
+            | Source::Chunk(source, _, _) => source.root_mut(),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+type Channels = Vec<Vec<Event>>;
+
+impl Cycle {
+    // apply the given function on the cycle's source
+    fn transformed<F>(self, fun: F) -> Self
+    where
+        F: FnOnce(Source) -> Source,
+    {
+        Self {
+            source: fun(self.source),
+            ..self
+        }
+    }
+
+    // speed up the cycle by the given factor, which must be > 0
+    pub(crate) fn fast(self, factor: Fraction) -> Self {
+        assert!(factor > Fraction::zero(), "fast factor must be > 0");
+        self.transformed(|source| Source::Fast(Box::new(source), factor))
+    }
+
+    // slow down the cycle by the given factor, which must be > 0
+    pub(crate) fn slow(self, factor: Fraction) -> Self {
+        assert!(factor > Fraction::zero(), "slow factor must be > 0");
+        self.fast(Fraction::one() / factor)
+    }
+
+    // reverse the events within each cycle
+    pub(crate) fn rev(self) -> Self {
+        self.transformed(|source| Source::Rev(Box::new(source)))
+    }
+
+    // apply the given transformed cycle every nth cycle, starting with the first one
+    pub(crate) fn every(self, n: usize, transformed: Cycle) -> Self {
+        assert!(n > 0, "every count must be > 0");
+        self.transformed(|source| Source::Every(Box::new(source), n, Box::new(transformed.source)))
+    }
+
+    // stack the cycle with a transformed copy of itself, shifted in time by the given amount.
+    // the given function receives the shifted cycle and returns the transformed one.
+    pub(crate) fn off<F>(self, time: Fraction, fun: F) -> Self
+    where
+        F: FnOnce(Cycle) -> Cycle,
+    {
+        let shifted = fun(self
+            .clone()
+            .transformed(|source| Source::Shift(Box::new(source), time)));
+        self.transformed(|source| Source::Stack(vec![source, shifted.source]))
+    }
+
+    // randomly remove events with the given probability in range [0, 1]
+    pub(crate) fn degrade_by(self, amount: f64) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        self.transformed(|source| Source::DegradeBy(Box::new(source), amount))
+    }
+
+    // play the cycle forwards, then backwards in the next cycle
+    pub(crate) fn palindrome(self) -> Self {
+        self.transformed(|source| Source::Palindrome(Box::new(source)))
+    }
+
+    // divide the cycle into n parts, and shift the start of the cycle by one part in each cycle
+    pub(crate) fn iter(self, n: usize) -> Self {
+        assert!(n > 0, "iter count must be > 0");
+        self.transformed(|source| Source::Iter(Box::new(source), n))
+    }
+
+    // divide the cycle into n parts, and apply the transformed cycle to one part in each cycle
+    pub(crate) fn chunk(self, n: usize, transformed: Cycle) -> Self {
+        assert!(n > 0, "chunk count must be > 0");
+        self.transformed(|source| Source::Chunk(Box::new(source), n, Box::new(transformed.source)))
+    }
+
+    // output events of a transformed source which start within the given cycle, with spans
+    // relative to the cycle's start
+    pub(super) fn output_source(&self, source: &Source, cycle: usize) -> Channels {
+        let start = Fraction::from(cycle as u64);
+        let mut channels = self.query_source(source, start, start + Fraction::one());
+        for channel in &mut channels {
+            for event in channel.iter_mut() {
+                event.span = Span::new(event.span.start - start, event.span.end - start);
+            }
+        }
+        channels
+    }
+
+    // create the random number generator for events which start at the given time
+    fn event_rng(&self, start: Fraction) -> Xoshiro256PlusPlus {
+        let numer = start.numer().copied().unwrap_or(0);
+        let denom = start.denom().copied().unwrap_or(1);
+        Xoshiro256PlusPlus::seed_from_u64(Cycle::mix_seed(
+            Cycle::mix_seed(self.rng_seed, numer),
+            denom,
+        ))
+    }
+
+    // query all events of the source which start within the given window in absolute cycle time
+    fn query_source(&self, source: &Source, start: Fraction, end: Fraction) -> Channels {
+        let start = start.max(Fraction::zero());
+        if end <= start {
+            return vec![];
+        }
+        match source {
+            Source::Mini(step) => self.query_cycles(start, end, |cycle, _, _| {
+                // merge holds, so transformations move or cut notes with their holds
+                let offset = Fraction::from(cycle as u64);
+                let mut channels: Channels = self
+                    .output_step(step, cycle)
+                    .iter()
+                    .map(Events::merge_holds)
+                    .collect();
+                Cycle::shift_spans(&mut channels, offset);
+                channels
+            }),
+            Source::Fast(source, factor) => {
+                let mut channels = self.query_source(source, start * *factor, end * *factor);
+                for channel in &mut channels {
+                    for event in channel.iter_mut() {
+                        event.span =
+                            Span::new(event.span.start / *factor, event.span.end / *factor);
+                        event.length = event.span.length();
+                    }
+                }
+                channels
+            }
+            Source::Shift(source, time) => {
+                let mut channels = self.query_source(source, start - *time, end - *time);
+                Cycle::shift_spans(&mut channels, *time);
+                channels
+            }
+            Source::Rev(source) => {
+                self.query_cycles(start, end, |cycle, _, _| self.query_rev(source, cycle))
+            }
+            Source::Stack(sources) => sources
+                .iter()
+                .flat_map(|source| self.query_source(source, start, end))
+                .collect(),
+            Source::Every(source, n, transformed) => {
+                self.query_cycles(start, end, |cycle, start, end| {
+                    if cycle % n == 0 {
+                        self.query_source(transformed, start, end)
+                    } else {
+                        self.query_source(source, start, end)
+                    }
+                })
+            }
+            Source::DegradeBy(source, amount) => {
+                let mut channels = self.query_source(source, start, end);
+                for channel in &mut channels {
+                    channel.retain(|event| {
+                        self.event_rng(event.span.start).gen_range(0.0..1.0) >= *amount
+                    });
+                }
+                channels
+            }
+            Source::Palindrome(source) => self.query_cycles(start, end, |cycle, _, _| {
+                // play the source's cycles alternating forwards and backwards
+                let source_cycle = cycle / 2;
+                let mut channels = if cycle % 2 == 0 {
+                    let source_start = Fraction::from(source_cycle as u64);
+                    self.query_source(source, source_start, source_start + Fraction::one())
+                } else {
+                    self.query_rev(source, source_cycle)
+                };
+                Cycle::shift_spans(&mut channels, Fraction::from((cycle - source_cycle) as u64));
+                channels
+            }),
+            Source::Iter(source, n) => self.query_cycles(start, end, |cycle, start, end| {
+                let shift = Fraction::from((cycle % n) as u64) / Fraction::from(*n as u64);
+                let mut channels = self.query_source(source, start + shift, end + shift);
+                Cycle::shift_spans(&mut channels, -shift);
+                channels
+            }),
+            Source::Chunk(source, n, transformed) => {
+                self.query_cycles(start, end, |cycle, start, end| {
+                    let part_length = Fraction::one() / Fraction::from(*n as u64);
+                    let part_start = Fraction::from(cycle as u64)
+                        + part_length * Fraction::from((cycle % n) as u64);
+                    let part_end = part_start + part_length;
+                    let in_part = |event: &Event| {
+                        event.span.start >= part_start && event.span.start < part_end
+                    };
+                    let mut channels = self.query_source(source, start, end);
+                    for channel in &mut channels {
+                        channel.retain(|event| !in_part(event));
+                    }
+                    let mut transformed_channels = self.query_source(transformed, start, end);
+                    for (index, channel) in transformed_channels.iter_mut().enumerate() {
+                        channel.retain(in_part);
+                        if channels.len() <= index {
+                            channels.push(vec![]);
+                        }
+                        channels[index].append(channel);
+                        channels[index].sort_by_key(|event| event.span.start);
+                    }
+                    channels
+                })
+            }
+        }
+    }
+
+    // query a single cycle of the source with reversed event spans
+    fn query_rev(&self, source: &Source, cycle: usize) -> Channels {
+        let start = Fraction::from(cycle as u64);
+        let end = start + Fraction::one();
+        let mut channels = self.query_source(source, start, end);
+        for channel in &mut channels {
+            for event in channel.iter_mut() {
+                event.span =
+                    Span::new(start + end - event.span.end, start + end - event.span.start);
+            }
+            channel.retain(|event| event.span.start >= start);
+            channel.reverse();
+        }
+        channels
+    }
+
+    // query the given window cycle by cycle, passing the cycle and the window within the cycle
+    // to the given function. only events which start within the window are kept.
+    fn query_cycles<F>(&self, start: Fraction, end: Fraction, mut fun: F) -> Channels
+    where
+        F: FnMut(usize, Fraction, Fraction) -> Channels,
+    {
+        let mut channels: Channels = vec![];
+        let first = start.floor().to_usize().unwrap_or(0);
+        let last = end.ceil().to_usize().unwrap_or(first);
+        for cycle in first..last {
+            let cycle_start = Fraction::from(cycle as u64).max(start);
+            let cycle_end = Fraction::from(cycle as u64 + 1).min(end);
+            for (index, mut channel) in fun(cycle, cycle_start, cycle_end).into_iter().enumerate() {
+                channel.retain(|event| {
+                    event.span.start >= cycle_start && event.span.start < cycle_end
+                });
+                if channels.len() <= index {
+                    channels.push(vec![]);
+                }
+                channels[index].append(&mut channel);
+            }
+        }
+        channels
+    }
+
+    // move all event spans by the given amount of cycles
+    fn shift_spans(channels: &mut Channels, offset: Fraction) {
+        for channel in channels {
+            for event in channel.iter_mut() {
+                event.span = Span::new(event.span.start + offset, event.span.end + offset);
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fraction::Fraction as F;
+
+    // values of all events in the first channel of the given cycle
+    fn cycle_events(cycle: &Cycle, index: usize) -> Vec<(String, F, F)> {
+        cycle
+            .output_cycle(index)
+            .first()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .map(|event| (event.value.to_string(), event.span.start, event.span.end))
+            .collect()
+    }
+
+    fn values(cycle: &Cycle, index: usize) -> Vec<String> {
+        cycle_events(cycle, index)
+            .into_iter()
+            .map(|(value, _, _)| value)
+            .collect()
+    }
+
+    fn all_values(cycle: &Cycle, index: usize) -> Vec<Vec<String>> {
+        cycle
+            .output_cycle(index)
+            .iter()
+            .map(|channel| channel.iter().map(|e| e.value.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn transforms() -> Result<(), String> {
+        let cycle = |input: &str| Cycle::from(input, Some([0; 32]));
+
+        let fast = cycle("a b")?.fast(F::from(2));
+        assert_eq!(values(&fast, 0), ["a4", "b4", "a4", "b4"]);
+        assert_eq!(
+            cycle_events(&fast, 1)[1],
+            ("b4".to_string(), F::new(1u8, 4u8), F::new(1u8, 2u8))
+        );
+
+        let slow = cycle("a b c d")?.slow(F::from(2));
+        assert_eq!(values(&slow, 0), ["a4", "b4"]);
+        assert_eq!(values(&slow, 1), ["c4", "d4"]);
+        assert_eq!(
+            cycle_events(&slow, 1)[1],
+            ("d4".to_string(), F::new(1u8, 2u8), F::from(1))
+        );
+
+        let rev = cycle("a _ b <c d>")?.rev();
+        assert_eq!(
+            cycle_events(&rev, 0),
+            [
+                ("c4".to_string(), F::from(0), F::new(1u8, 4u8)),
+                ("b4".to_string(), F::new(1u8, 4u8), F::new(1u8, 2u8)),
+                ("a4".to_string(), F::new(1u8, 2u8), F::from(1)),
+            ]
+        );
+        assert_eq!(values(&rev, 1), ["d4", "b4", "a4"]);
+
+        let every = cycle("a b")?.every(3, cycle("a b")?.fast(F::from(2)));
+        assert_eq!(values(&every, 0), ["a4", "b4", "a4", "b4"]);
+        assert_eq!(values(&every, 1), ["a4", "b4"]);
+        assert_eq!(values(&every, 2), ["a4", "b4"]);
+        assert_eq!(values(&every, 3), ["a4", "b4", "a4", "b4"]);
+
+        let off = cycle("a b")?.off(F::new(1u8, 4u8), |c| c);
+        assert_eq!(all_values(&off, 0), [vec!["a4", "b4"], vec!["a4", "b4"]]);
+        assert_eq!(
+            cycle_events(&off, 1),
+            [
+                ("a4".to_string(), F::from(0), F::new(1u8, 2u8)),
+                ("b4".to_string(), F::new(1u8, 2u8), F::from(1)),
+            ]
+        );
+        assert_eq!(
+            off.output_cycle(1)[1][1],
+            Event::at(F::new(3u8, 4u8), F::new(1u8, 2u8)).note(11, 4)
+        );
+        let off = cycle("a b")?.off(F::new(1u8, 4u8), |c| c.rev());
+        assert_eq!(all_values(&off, 1), [vec!["a4", "b4"], vec!["a4"]]);
+
+        let palindrome = cycle("a b c")?.palindrome();
+        assert_eq!(values(&palindrome, 0), ["a4", "b4", "c4"]);
+        assert_eq!(values(&palindrome, 1), ["c4", "b4", "a4"]);
+        assert_eq!(values(&palindrome, 2), ["a4", "b4", "c4"]);
+
+        let iter = cycle("a b c d")?.iter(4);
+        assert_eq!(values(&iter, 0), ["a4", "b4", "c4", "d4"]);
+        assert_eq!(values(&iter, 1), ["b4", "c4", "d4", "a4"]);
+        assert_eq!(values(&iter, 3), ["d4", "a4", "b4", "c4"]);
+        assert_eq!(values(&iter, 4), ["a4", "b4", "c4", "d4"]);
+
+        let chunk = cycle("a b c d")?.chunk(4, cycle("a b c d")?.fast(F::from(2)));
+        assert_eq!(values(&chunk, 0), ["a4", "b4", "b4", "c4", "d4"]);
+        assert_eq!(values(&chunk, 1), ["a4", "c4", "d4", "c4", "d4"]);
+
+        let degraded = cycle("a*16")?.degrade_by(0.5);
+        let count = values(&degraded, 0).len();
+        assert!(count > 0 && count < 16);
+        assert_eq!(values(&degraded, 0), values(&degraded, 0));
+        assert!(values(&cycle("a*16")?.degrade_by(0.0), 0).len() == 16);
+        assert!(values(&cycle("a*16")?.degrade_by(1.0), 0).is_empty());
+
+        // transforms compose
+        let composed = cycle("a b")?.fast(F::from(2)).rev().slow(F::from(2));
+        assert_eq!(values(&composed, 0), ["b4", "a4"]);
+        Ok(())
+    }
+}
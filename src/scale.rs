@@ -1,6 +1,8 @@
 //! Musical scales based on `Note` and custom intervals or common scale names.
 
-use crate::Note;
+use std::borrow::Cow;
+
+use crate::{tuning::Tuning, Note};
 
 // -------------------------------------------------------------------------------------------------
 
@@ -8,7 +10,7 @@ use crate::Note;
 struct Mode {
     name: &'static str,
     alt_names: &'static str,
-    degrees: [usize; 12],
+    degrees: Cow<'static, [usize]>,
 }
 
 impl TryFrom<&str> for Mode {
@@ -45,7 +47,7 @@ impl TryFrom<&Vec<i32>> for Mode {
         } else if intervals.windows(2).any(|f| f[0] > f[1]) {
             Err("interval list must be sorted in ascending order".to_string())
         } else {
-            let mut degrees = [0; 12];
+            let mut degrees = vec![0; 12];
             for (degree_count, i) in intervals.iter().enumerate() {
                 if !(0..12).contains(i) {
                     return Err(format!("intervals must be in range [0..12] but one is '{}'", i));
@@ -55,13 +57,52 @@ impl TryFrom<&Vec<i32>> for Mode {
             Ok(Self {
                 name: "custom scale",
                 alt_names: "",
-                degrees,
+                degrees: Cow::Owned(degrees),
             })
         }
     }
 }
 
+impl TryFrom<(Note, &Tuning)> for Mode {
+    type Error = String;
+
+    /// Try converting the given tuning's mapped keys to a scale which contains all its degrees,
+    /// starting from the given root key
+    fn try_from((root, tuning): (Note, &Tuning)) -> Result<Self, String> {
+        let key_degrees = tuning.key_degrees();
+        if key_degrees.iter().all(Option::is_none) {
+            return Err("tuning has no mapped keys".to_string());
+        }
+        // key degrees start at the mapping's middle note: rotate them to start at the root
+        let period = key_degrees.len() as i32;
+        let root_offset = (root as i32 - tuning.mapping().middle_note() as i32).rem_euclid(period);
+        if key_degrees[root_offset as usize].is_none() {
+            return Err(format!("root key '{}' is not mapped in the tuning", root));
+        }
+        let mut degree_count = 0;
+        let degrees = (0..period)
+            .map(|step| {
+                if key_degrees[((root_offset + step) % period) as usize].is_some() {
+                    degree_count += 1;
+                    degree_count
+                } else {
+                    0
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(Self {
+            name: "tuning",
+            alt_names: "",
+            degrees: Cow::Owned(degrees),
+        })
+    }
+}
+
 impl Mode {
+    fn period(&self) -> usize {
+        self.degrees.len()
+    }
+
     fn steps(&self) -> Vec<usize> {
         self.degrees
             .iter()
@@ -102,183 +143,183 @@ const SCALE_MODES: [Mode; 36] = [
     Mode {
         name: "chromatic",
         alt_names: "all",
-        degrees: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12],
+        degrees: Cow::Borrowed(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
     },
     Mode {
         name: "natural major",
         alt_names: "major;ionian",
-        degrees: [1, 0, 2, 0, 3, 4, 0, 5, 0, 6, 0, 7],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 4, 0, 5, 0, 6, 0, 7]),
         // [0,2,4,5,7,9,10]
     },
     Mode {
         name: "natural minor",
         alt_names: "minor;aeolian",
-        degrees: [1, 0, 2, 3, 0, 4, 0, 5, 6, 0, 7, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 0, 4, 0, 5, 6, 0, 7, 0]),
     },
     Mode {
         name: "pentatonic major",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 0, 0, 4, 0, 5, 0, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 0, 0, 4, 0, 5, 0, 0]),
     },
     Mode {
         name: "pentatonic minor",
         alt_names: "",
-        degrees: [1, 0, 0, 2, 0, 3, 0, 4, 0, 0, 5, 0],
+        degrees: Cow::Borrowed(&[1, 0, 0, 2, 0, 3, 0, 4, 0, 0, 5, 0]),
     },
     Mode {
         name: "pentatonic egyptian",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 0, 3, 0, 4, 0, 0, 5, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 0, 3, 0, 4, 0, 0, 5, 0]),
     },
     Mode {
         name: "blues major",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 4, 0, 0, 5, 0, 6, 0, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 4, 0, 0, 5, 0, 6, 0, 0]),
     },
     Mode {
         name: "blues minor",
         alt_names: "",
-        degrees: [1, 0, 0, 2, 0, 3, 4, 5, 0, 0, 6, 0],
+        degrees: Cow::Borrowed(&[1, 0, 0, 2, 0, 3, 4, 5, 0, 0, 6, 0]),
     },
     Mode {
         name: "whole tone",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0]),
     },
     Mode {
         name: "augmented",
         alt_names: "",
-        degrees: [1, 0, 0, 2, 3, 0, 0, 4, 5, 0, 0, 6],
+        degrees: Cow::Borrowed(&[1, 0, 0, 2, 3, 0, 0, 4, 5, 0, 0, 6]),
     },
     Mode {
         name: "prometheus",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 0, 4, 0, 0, 5, 6, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 0, 4, 0, 0, 5, 6, 0]),
     },
     Mode {
         name: "tritone",
         alt_names: "",
-        degrees: [1, 2, 0, 0, 3, 0, 4, 5, 0, 0, 6, 0],
+        degrees: Cow::Borrowed(&[1, 2, 0, 0, 3, 0, 4, 5, 0, 0, 6, 0]),
     },
     Mode {
         name: "harmonic major",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 4, 0, 5, 6, 0, 0, 7],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 4, 0, 5, 6, 0, 0, 7]),
     },
     Mode {
         name: "harmonic minor",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 0, 4, 0, 5, 6, 0, 0, 7],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 0, 4, 0, 5, 6, 0, 0, 7]),
     },
     Mode {
         name: "melodic minor",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 0, 4, 0, 5, 0, 6, 0, 7],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 0, 4, 0, 5, 0, 6, 0, 7]),
     },
     Mode {
         name: "all minor",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 0, 4, 0, 5, 6, 6, 7, 7],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 0, 4, 0, 5, 6, 6, 7, 7]),
     },
     Mode {
         name: "dorian",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 0, 4, 0, 5, 0, 6, 7, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 0, 4, 0, 5, 0, 6, 7, 0]),
     },
     Mode {
         name: "phrygian",
         alt_names: "",
-        degrees: [1, 2, 0, 3, 0, 4, 0, 5, 6, 0, 7, 0],
+        degrees: Cow::Borrowed(&[1, 2, 0, 3, 0, 4, 0, 5, 6, 0, 7, 0]),
     },
     Mode {
         name: "phrygian dominant",
         alt_names: "",
-        degrees: [1, 2, 0, 0, 3, 4, 0, 5, 6, 0, 7, 0],
+        degrees: Cow::Borrowed(&[1, 2, 0, 0, 3, 4, 0, 5, 6, 0, 7, 0]),
     },
     Mode {
         name: "lydian",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 0, 4, 5, 0, 6, 0, 7],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 0, 4, 5, 0, 6, 0, 7]),
     },
     Mode {
         name: "lydian augmented",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 0, 4, 0, 5, 6, 0, 7],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 0, 4, 0, 5, 6, 0, 7]),
     },
     Mode {
         name: "mixolydian",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 4, 0, 5, 0, 6, 7, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 4, 0, 5, 0, 6, 7, 0]),
     },
     Mode {
         name: "locrian",
         alt_names: "",
-        degrees: [1, 2, 0, 3, 0, 4, 5, 0, 6, 0, 7, 0],
+        degrees: Cow::Borrowed(&[1, 2, 0, 3, 0, 4, 5, 0, 6, 0, 7, 0]),
     },
     Mode {
         name: "locrian major",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 4, 5, 0, 6, 0, 7, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 4, 5, 0, 6, 0, 7, 0]),
     },
     Mode {
         name: "super locrian",
         alt_names: "",
-        degrees: [1, 2, 0, 3, 4, 0, 5, 0, 6, 0, 7, 0],
+        degrees: Cow::Borrowed(&[1, 2, 0, 3, 4, 0, 5, 0, 6, 0, 7, 0]),
     },
     Mode {
         name: "neapolitan major",
         alt_names: "",
-        degrees: [1, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0, 7],
+        degrees: Cow::Borrowed(&[1, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0, 7]),
     },
     Mode {
         name: "neapolitan minor",
         alt_names: "",
-        degrees: [1, 2, 0, 3, 0, 4, 0, 5, 6, 0, 0, 7],
+        degrees: Cow::Borrowed(&[1, 2, 0, 3, 0, 4, 0, 5, 6, 0, 0, 7]),
     },
     Mode {
         name: "romanian minor",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 0, 0, 4, 5, 0, 6, 7, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 0, 0, 4, 5, 0, 6, 7, 0]),
     },
     Mode {
         name: "spanish gypsy",
         alt_names: "",
-        degrees: [1, 2, 0, 0, 3, 4, 0, 5, 6, 0, 0, 7],
+        degrees: Cow::Borrowed(&[1, 2, 0, 0, 3, 4, 0, 5, 6, 0, 0, 7]),
     },
     Mode {
         name: "hungarian gypsy",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 0, 0, 4, 5, 6, 0, 0, 7],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 0, 0, 4, 5, 6, 0, 0, 7]),
     },
     Mode {
         name: "enigmatic",
         alt_names: "",
-        degrees: [1, 2, 0, 0, 3, 0, 4, 0, 5, 0, 6, 7],
+        degrees: Cow::Borrowed(&[1, 2, 0, 0, 3, 0, 4, 0, 5, 0, 6, 7]),
     },
     Mode {
         name: "overtone",
         alt_names: "",
-        degrees: [1, 0, 2, 0, 3, 0, 4, 5, 0, 6, 7, 0],
+        degrees: Cow::Borrowed(&[1, 0, 2, 0, 3, 0, 4, 5, 0, 6, 7, 0]),
     },
     Mode {
         name: "diminished half",
         alt_names: "",
-        degrees: [1, 2, 0, 3, 4, 0, 5, 6, 0, 7, 8, 0],
+        degrees: Cow::Borrowed(&[1, 2, 0, 3, 4, 0, 5, 6, 0, 7, 8, 0]),
     },
     Mode {
         name: "diminished whole",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 0, 4, 5, 0, 6, 7, 0, 8],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 0, 4, 5, 0, 6, 7, 0, 8]),
     },
     Mode {
         name: "spanish eight-tone",
         alt_names: "eight-tone",
-        degrees: [1, 2, 0, 3, 4, 5, 6, 0, 7, 0, 8, 0],
+        degrees: Cow::Borrowed(&[1, 2, 0, 3, 4, 5, 6, 0, 7, 0, 8, 0]),
     },
     Mode {
         name: "nine-tone",
         alt_names: "",
-        degrees: [1, 0, 2, 3, 4, 0, 5, 6, 7, 8, 0, 9],
+        degrees: Cow::Borrowed(&[1, 0, 2, 3, 4, 0, 5, 6, 7, 8, 0, 9]),
     },
 ];
 
@@ -299,16 +340,19 @@ pub enum TransposeStrictness {
 #[derive(Debug, Clone)]
 pub struct ScaleNoteIter {
     root: u8,
-    octave: u8,
+    period: usize,
+    octave: usize,
     steps: Vec<usize>,
     step_index: usize,
 }
 
 impl ScaleNoteIter {
-    fn new(root: u8, octave: u8, steps: Vec<usize>) -> Self {
+    fn new(root: u8, period: usize, steps: Vec<usize>) -> Self {
+        let octave = 0;
         let step_index = 0;
         Self {
             root,
+            period,
             octave,
             steps,
             step_index,
@@ -320,7 +364,7 @@ impl Iterator for ScaleNoteIter {
     type Item = Note;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let key = self.root as usize + 12 * self.octave as usize + self.steps[self.step_index];
+        let key = self.root as usize + self.period * self.octave + self.steps[self.step_index];
         self.step_index += 1;
         if self.step_index >= self.steps.len() {
             self.octave += 1;
@@ -337,10 +381,12 @@ impl Iterator for ScaleNoteIter {
 // -------------------------------------------------------------------------------------------------
 
 /// A musical scale / mode.
+///
+/// Scales usually are based on the 12 keys of an octave. Scales created from a [`Tuning`] use
+/// the tuning's mapped keys instead, so their notes need to be played back with the tuning.
 #[derive(Debug, Clone)]
 pub struct Scale {
-    key: u8,    // 0..12
-    octave: u8, // 0..10
+    root: u8, // 0..128
     mode: Mode,
 }
 
@@ -349,8 +395,7 @@ impl TryFrom<(Note, &str)> for Scale {
 
     fn try_from((note, mode): (Note, &str)) -> Result<Self, String> {
         Ok(Self {
-            key: note.key(),
-            octave: note.octave(),
+            root: note as u8,
             mode: Mode::try_from(mode)?,
         })
     }
@@ -361,19 +406,30 @@ impl TryFrom<(Note, &Vec<i32>)> for Scale {
 
     fn try_from((note, intervals): (Note, &Vec<i32>)) -> Result<Self, String> {
         Ok(Self {
-            key: note.key(),
-            octave: note.octave(),
+            root: note as u8,
             mode: Mode::try_from(intervals)?,
         })
     }
 }
 
+impl TryFrom<(Note, &Tuning)> for Scale {
+    type Error = String;
+
+    /// Create a scale with all mapped keys of the given tuning, starting from the given note.
+    /// Fails when the tuning has no mapped keys or the note is not mapped in the tuning.
+    fn try_from((note, tuning): (Note, &Tuning)) -> Result<Self, String> {
+        Ok(Self {
+            root: note as u8,
+            mode: Mode::try_from((note, tuning))?,
+        })
+    }
+}
+
 impl Scale {
     #[allow(dead_code)] // used in tests only
     fn new(note: Note, mode: Mode) -> Self {
-        let key = note.key();
-        let octave = note.octave();
-        Self { key, octave, mode }
+        let root = note as u8;
+        Self { root, mode }
     }
 
     /// Known mode/scaling names.
@@ -381,9 +437,14 @@ impl Scale {
         SCALE_MODES.iter().map(|mode| mode.name).collect()
     }
 
-    /// Key note as number [0..12].
+    /// Key note as number [0..12], or [0..period] for scales with a custom period.
     pub fn key(&self) -> u8 {
-        self.key
+        (self.root as usize % self.mode.period()) as u8
+    }
+
+    /// Number of keys in the scale's period: 12 for all common scales.
+    pub fn period(&self) -> usize {
+        self.mode.period()
     }
 
     /// List of raw degrees where 0 indicates no step.
@@ -426,7 +487,7 @@ impl Scale {
 
     /// Iterator with ascending list of notes in the scale
     pub fn notes_iter(&self) -> ScaleNoteIter {
-        ScaleNoteIter::new(self.key, self.octave, self.steps())
+        ScaleNoteIter::new(self.root, self.mode.period(), self.steps())
     }
 
     /// Generate an ascending list of notes in the scale, using the Note passed in the
@@ -435,7 +496,7 @@ impl Scale {
     pub fn notes(&self) -> Vec<Note> {
         self.steps()
             .into_iter()
-            .map(|d| d + (self.key as usize) + (12 * self.octave as usize))
+            .map(|d| d + self.root as usize)
             .map(|n| Note::from(n.min(0x7f) as u8))
             .collect()
     }
@@ -471,7 +532,7 @@ impl Scale {
                 }
             } else {
                 let transposed_root_degree =
-                    self.mode.degrees[self.transposed_note_to_step(self.key as i32 + offset)];
+                    self.mode.degrees[self.transposed_note_to_step(self.root as i32 + offset)];
                 let transposed_root_in_scale = transposed_root_degree != 0;
                 if transposed_root_in_scale
                     || strictness != TransposeStrictness::ForceSecondaryInScaleNotesWhenRootInScale
@@ -489,8 +550,11 @@ impl Scale {
     }
 
     fn degree_to_step(&self, degree: usize) -> usize {
-        assert!((1..=12).contains(&degree), "Degree out of bounds");
-        for i in 0..12 {
+        assert!(
+            (1..=self.mode.period()).contains(&degree),
+            "Degree out of bounds"
+        );
+        for i in 0..self.mode.period() {
             if self.mode.degrees[i] == degree {
                 return i;
             }
@@ -499,23 +563,25 @@ impl Scale {
     }
 
     fn transposed_note_to_step(&self, note: i32) -> usize {
-        let offset = note - self.key as i32;
+        let period = self.mode.period() as i32;
+        let offset = note - self.root as i32;
         let mut offset = offset;
         while offset < 0 {
-            offset += 12;
+            offset += period;
         }
-        (offset % 12) as usize
+        (offset % period) as usize
     }
 
     fn quantize_note(&self, note: i32) -> i32 {
-        self.quantize_offset(note - self.key as i32) + self.key as i32
+        self.quantize_offset(note - self.root as i32) + self.root as i32
     }
 
     fn quantize_offset(&self, offset: i32) -> i32 {
-        let step = self.transposed_note_to_step(self.key as i32 + offset);
+        let period = self.mode.period();
+        let step = self.transposed_note_to_step(self.root as i32 + offset);
         let mut qstep = step;
         while self.mode.degrees[qstep] == 0 {
-            qstep = (qstep + 12 - 1) % 12;
+            qstep = (qstep + period - 1) % period;
         }
         offset - step as i32 + qstep as i32
     }
@@ -526,15 +592,16 @@ impl Scale {
             "Original must have been in-scale to transpose."
         );
         let quantized_offset = self.quantize_offset(offset);
+        let period = self.mode.period() as i32;
 
         // count distance root moved in degrees
         let num_degrees = self.mode.steps().len();
         let mut degree_diff = 0;
         if quantized_offset < 0 {
-            let whole_octaves = -quantized_offset / 12;
+            let whole_octaves = -quantized_offset / period;
             degree_diff -= whole_octaves * num_degrees as i32;
-            let mut remainder = -quantized_offset % 12;
-            for i in (0..12).rev() {
+            let mut remainder = -quantized_offset % period;
+            for i in (0..period as usize).rev() {
                 if self.mode.degrees[i] != 0 {
                     remainder -= 1;
                     if remainder > 0 {
@@ -543,10 +610,10 @@ impl Scale {
                 }
             }
         } else {
-            let whole_octaves = quantized_offset / 12;
+            let whole_octaves = quantized_offset / period;
             degree_diff += whole_octaves * num_degrees as i32;
-            let mut remainder = quantized_offset % 12;
-            for i in 1..12 {
+            let mut remainder = quantized_offset % period;
+            for i in 1..period as usize {
                 if self.mode.degrees[i] != 0 {
                     remainder -= 1;
                     if remainder > 0 {
@@ -572,7 +639,7 @@ impl Scale {
         }
 
         let transposed_note_scale_step = self.degree_to_step(transposed_degree as usize);
-        note as i32 - scale_step as i32 + transposed_note_scale_step as i32 + octave_offset * 12
+        note as i32 - scale_step as i32 + transposed_note_scale_step as i32 + octave_offset * period
     }
 }
 
@@ -661,6 +728,42 @@ mod test {
         );
     }
 
+    #[test]
+    fn tuning() -> Result<(), String> {
+        // all keys of a linear mapped tuning are in the scale
+        let scale = Scale::try_from((Note::C5, &Tuning::equal_temperament(19)))?;
+        assert_eq!(scale.period(), 19);
+        assert_eq!(scale.key(), 60 % 19);
+        assert_eq!(
+            scale.notes(),
+            (60..79_u8).map(Note::from).collect::<Vec<_>>()
+        );
+        assert_eq!(scale.transpose(Note::C5, 19), Note::from(79_u8));
+
+        // unmapped keys are not
+        let tuning = Tuning::from_scl("just\n7\n9/8\n5/4\n4/3\n3/2\n5/3\n15/8\n2/1\n")?
+            .with_mapping(crate::tuning::KeyboardMapping::from_kbm(
+                "12\n0\n127\n60\n69\n440.0\n7\n0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\n6\n",
+            )?);
+        let scale = Scale::try_from((Note::C5, &tuning))?;
+        assert_eq!(scale.degrees(), Mode::try_from("major")?.degrees.to_vec());
+        assert_eq!(scale.transpose(Note::Cs5, 0), Note::C5);
+
+        // degrees start at the scale's root, not at the mapping's middle note
+        let scale = Scale::try_from((Note::D5, &tuning))?;
+        assert_eq!(scale.degrees(), Mode::try_from("dorian")?.degrees.to_vec());
+        assert_eq!(scale.transpose(Note::Ds5, 0), Note::D5);
+        assert_eq!(scale.transpose(Note::D5, 2), Note::E5);
+
+        // unmapped roots and tunings without mapped keys are rejected
+        assert!(Scale::try_from((Note::Cs5, &tuning)).is_err());
+        let tuning = tuning.with_mapping(crate::tuning::KeyboardMapping::from_kbm(
+            "2\n0\n127\n60\n69\n440.0\n7\nx\nx\n",
+        )?);
+        assert!(Scale::try_from((Note::C5, &tuning)).is_err());
+        Ok(())
+    }
+
     #[test]
     fn chord() -> Result<(), String> {
         let scale = Scale::new(Note::C4, Mode::try_from("major")?);
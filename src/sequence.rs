@@ -61,6 +61,7 @@ impl Sequence {
         for phrase in &mut self.phrases {
             phrase.set_time_base(time_base);
         }
+        self.time_base = *time_base;
     }
 
     /// Reset all rhythms in our phrases to their initial state.
@@ -78,6 +79,9 @@ impl Sequence {
 
     /// Run rhythms until a given sample time is reached, calling the given `visitor`
     /// function for all emitted events to consume them.
+    ///
+    /// Emitted [`TempoChangeEvent`](crate::event::TempoChangeEvent)s get applied to all phrases
+    /// at the event's sample time.
     pub fn emit_until_time<F>(&mut self, run_until_time: SampleTime, consumer: &mut F)
     where
         F: FnMut(RhythmIndex, SampleTime, Option<Event>, SampleTime),
@@ -92,10 +96,15 @@ impl Sequence {
             let next_phrase_start = phrase_length_in_samples - self.sample_position_in_phrase;
             let samples_to_run = run_until_time - self.sample_position;
             if next_phrase_start <= samples_to_run {
-                // run current phrase until it ends
+                // run current phrase until it ends or changes the tempo
                 let sample_position = self.sample_position;
-                self.current_phrase_mut()
-                    .emit_until_time(sample_position + next_phrase_start, consumer);
+                if let Some((time, time_base)) = self
+                    .current_phrase_mut()
+                    .emit_until_time_or_tempo_change(sample_position + next_phrase_start, consumer)
+                {
+                    self.apply_tempo_change(time, &time_base);
+                    continue;
+                }
                 // select next phrase in the sequence
                 let previous_phrase = self.current_phrase_mut().clone();
                 self.phrase_index += 1;
@@ -111,16 +120,43 @@ impl Sequence {
                         .reset_with_offset(sample_offset, &previous_phrase);
                 }
             } else {
-                // keep running the current phrase
+                // keep running the current phrase until it changes the tempo
                 let sample_position = self.sample_position;
-                self.current_phrase_mut()
-                    .emit_until_time(sample_position + samples_to_run, consumer);
+                if let Some((time, time_base)) = self
+                    .current_phrase_mut()
+                    .emit_until_time_or_tempo_change(sample_position + samples_to_run, consumer)
+                {
+                    self.apply_tempo_change(time, &time_base);
+                    continue;
+                }
                 self.sample_position_in_phrase += samples_to_run;
                 self.sample_position += samples_to_run;
             }
         }
     }
 
+    /// Apply a tempo change, which got emitted and applied by the current phrase at the given
+    /// sample time, to our own playback position and all other phrases.
+    fn apply_tempo_change(&mut self, time: SampleTime, time_base: &BeatTimeBase) {
+        // move to the change's time
+        let time = time.max(self.sample_position);
+        self.sample_position_in_phrase += time - self.sample_position;
+        self.sample_position = time;
+        // rescale position in the current phrase, so the remaining phrase plays in the new tempo
+        let phrase_length = self.current_phrase().length();
+        let ratio = phrase_length.to_samples(time_base) / phrase_length.to_samples(&self.time_base);
+        self.sample_position_in_phrase =
+            (self.sample_position_in_phrase as f64 * ratio) as SampleTime;
+        self.time_base = *time_base;
+        // apply new time base to all other phrases
+        let phrase_index = self.phrase_index;
+        for (index, phrase) in self.phrases.iter_mut().enumerate() {
+            if index != phrase_index {
+                phrase.set_time_base(time_base);
+            }
+        }
+    }
+
     fn current_phrase(&self) -> &Phrase {
         &self.phrases[self.phrase_index]
     }
@@ -167,3 +203,185 @@ impl RhythmIter for Sequence {
 ///
 /// So this is **safe as long as new sequences only reference phrases within itself**.
 unsafe impl Send for Sequence {}
+
+// -------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        event::{new_tempo_change, Event},
+        prelude::*,
+    };
+
+    #[test]
+    fn tempo_changes() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 1000,
+        };
+        // change tempo to 240 bpm in the second beat, then back to 120 bpm in the next bar
+        let tempo_rhythm = time_base.every_nth_beat(1.0).trigger(
+            vec![
+                new_tempo_change(120.0, None),
+                new_tempo_change(240.0, 3),
+                new_tempo_change(None, None),
+                new_tempo_change(None, None),
+            ]
+            .to_event_sequence(),
+        );
+        let note_rhythm = time_base.every_nth_beat(1.0).trigger(new_note_event("c4"));
+        let phrase = Phrase::new(
+            time_base,
+            vec![
+                RhythmSlot::from(tempo_rhythm),
+                RhythmSlot::from(note_rhythm),
+            ],
+            BeatTimeStep::Bar(1.0),
+        );
+        let mut sequence = Sequence::new(time_base, vec![phrase]);
+
+        let mut note_times = vec![];
+        let mut tempo_changes = vec![];
+        sequence.emit_until_time(4000, &mut |_, time, event, duration| match event {
+            Some(Event::NoteEvents(_)) => note_times.push((time, duration)),
+            Some(Event::TempoChangeEvent(change)) => {
+                tempo_changes.push((time, change.beats_per_min))
+            }
+            _ => (),
+        });
+        // first beat at 120 bpm, then 240 bpm. The tempo rhythm's 4 changes repeat every
+        // 500 + 3 * 250 samples. Single phrases are not reset, so this is no phrase boundary:
+        // see `tempo_changes_in_phrases` for phrase switches.
+        assert_eq!(
+            note_times,
+            vec![
+                (0, 500),
+                (500, 250),
+                (750, 250),
+                (1000, 250),
+                (1250, 500),
+                (1750, 250),
+                (2000, 250),
+                (2250, 250),
+                (2500, 500),
+                (3000, 250),
+                (3250, 250),
+                (3500, 250),
+                (3750, 500),
+            ]
+        );
+        assert_eq!(
+            tempo_changes,
+            vec![
+                (0, Some(120.0)),
+                (500, Some(240.0)),
+                (750, None),
+                (1000, None),
+                (1250, Some(120.0)),
+                (1750, Some(240.0)),
+                (2000, None),
+                (2250, None),
+                (2500, Some(120.0)),
+                (3000, Some(240.0)),
+                (3250, None),
+                (3500, None),
+                (3750, Some(120.0)),
+            ]
+        );
+
+        // invalid tempos are ignored, too fast ones get clamped
+        for (beats_per_min, note_duration) in [
+            (f32::INFINITY, 500),
+            (f32::NAN, 500),
+            (
+                1e30,
+                (60_000.0 / TempoChangeEvent::MAX_BEATS_PER_MIN) as u64,
+            ),
+        ] {
+            let tempo_change = TempoChangeEvent {
+                beats_per_min: Some(beats_per_min),
+                beats_per_bar: None,
+            };
+            let phrase = Phrase::new(
+                time_base,
+                vec![
+                    RhythmSlot::from(
+                        time_base
+                            .every_nth_bar(1.0)
+                            .trigger(tempo_change.to_event()),
+                    ),
+                    RhythmSlot::from(time_base.every_nth_beat(1.0).trigger(new_note_event("c4"))),
+                ],
+                BeatTimeStep::Bar(1.0),
+            );
+            let mut sequence = Sequence::new(time_base, vec![phrase]);
+            let mut note_durations = vec![];
+            sequence.emit_until_time(1000, &mut |_, _, event, duration| {
+                if let Some(Event::NoteEvents(_)) = event {
+                    note_durations.push(duration);
+                }
+            });
+            assert!(!note_durations.is_empty());
+            assert!(note_durations.iter().all(|d| *d == note_duration));
+        }
+    }
+
+    #[test]
+    fn tempo_changes_in_phrases() {
+        let time_base = BeatTimeBase {
+            beats_per_min: 120.0,
+            beats_per_bar: 4,
+            samples_per_sec: 1000,
+        };
+        // the first phrase changes to 240 bpm in 3/4, the second one back to 120 bpm in 4/4
+        let new_phrase = |tempo_change: TempoChangeEvent, note| {
+            Phrase::new(
+                time_base,
+                vec![
+                    RhythmSlot::from(
+                        time_base
+                            .every_nth_bar(1.0)
+                            .trigger(tempo_change.to_event()),
+                    ),
+                    RhythmSlot::from(time_base.every_nth_beat(1.0).trigger(new_note_event(note))),
+                ],
+                BeatTimeStep::Bar(1.0),
+            )
+        };
+        let mut sequence = Sequence::new(
+            time_base,
+            vec![
+                new_phrase(new_tempo_change(240.0, 3), "c4"),
+                new_phrase(new_tempo_change(120.0, 4), "e4"),
+            ],
+        );
+
+        let mut notes = vec![];
+        sequence.emit_until_time(4000, &mut |_, time, event, duration| {
+            if let Some(Event::NoteEvents(note_events)) = event {
+                if let Some(Some(note_event)) = note_events.first() {
+                    notes.push((note_event.note, time, duration));
+                }
+            }
+        });
+        // the first phrase plays a bar of 3 beats with 250 samples, the second phrase then
+        // starts after 750 samples and plays a bar of 4 beats with 500 samples.
+        assert_eq!(
+            notes,
+            vec![
+                (Note::C4, 0, 250),
+                (Note::C4, 250, 250),
+                (Note::C4, 500, 250),
+                (Note::E4, 750, 500),
+                (Note::E4, 1250, 500),
+                (Note::E4, 1750, 500),
+                (Note::E4, 2250, 500),
+                (Note::C4, 2750, 250),
+                (Note::C4, 3000, 250),
+                (Note::C4, 3250, 250),
+                (Note::E4, 3500, 500),
+            ]
+        );
+    }
+}
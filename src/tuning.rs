@@ -0,0 +1,447 @@
+//! Microtonal tunings from Scala scale (.scl) and keyboard mapping (.kbm) files.
+
+use std::path::Path;
+
+use crate::Note;
+
+// -------------------------------------------------------------------------------------------------
+
+/// Frequency of the note key 60 in 12-TET with A = 440 Hz, the key which plays samples at their
+/// original speed.
+const DEFAULT_BASE_FREQUENCY: f64 = 261.625_565_300_598_6;
+
+// -------------------------------------------------------------------------------------------------
+
+// Iterate over all non-comment lines of a Scala file.
+fn scala_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.starts_with('!'))
+}
+
+// Parse the first white space separated token of a Scala file line.
+fn scala_value<T: std::str::FromStr>(line: Option<&str>, name: &str) -> Result<T, String> {
+    let line = line.ok_or_else(|| format!("missing {}", name))?;
+    let token = line.split_whitespace().next().unwrap_or("");
+    token
+        .parse::<T>()
+        .map_err(|_| format!("invalid {}: '{}'", name, token))
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// Keyboard mapping of a [`Tuning`], as defined in Scala keyboard mapping (.kbm) files.
+///
+/// Maps note keys to scale degrees of the tuning and defines the tuning's reference frequency.
+/// See <https://www.huygens-fokker.org/scala/help.htm#mappings> for details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardMapping {
+    first_note: u8,
+    last_note: u8,
+    middle_note: u8,
+    reference_note: u8,
+    reference_frequency: f64,
+    octave_degree: usize,
+    map: Vec<Option<usize>>, // empty for linear mappings
+}
+
+impl Default for KeyboardMapping {
+    /// Linear mapping with the scale's first degree on key 60 and A = 440 Hz on key 69.
+    fn default() -> Self {
+        Self {
+            first_note: 0,
+            last_note: 127,
+            middle_note: 60,
+            reference_note: 69,
+            reference_frequency: 440.0,
+            octave_degree: 0,
+            map: vec![],
+        }
+    }
+}
+
+impl KeyboardMapping {
+    /// Create a linear keyboard mapping, which maps the scale's first degree to the given middle
+    /// note and tunes the given reference note to the given frequency.
+    ///
+    /// ### Panics
+    /// Panics when the given frequency is not > 0.
+    pub fn linear(middle_note: Note, reference_note: Note, reference_frequency: f64) -> Self {
+        assert!(
+            reference_frequency > 0.0,
+            "Invalid reference frequency: frequency must be > 0"
+        );
+        Self {
+            middle_note: middle_note as u8,
+            reference_note: reference_note as u8,
+            reference_frequency,
+            ..Self::default()
+        }
+    }
+
+    /// Parse a keyboard mapping from the content of a Scala keyboard mapping (.kbm) file.
+    pub fn from_kbm(content: &str) -> Result<Self, String> {
+        let mut lines = scala_lines(content);
+        let size = scala_value::<usize>(lines.next(), "map size")?;
+        let note_value = |line: Option<&str>, name: &str| -> Result<u8, String> {
+            let note = scala_value::<u8>(line, name)?;
+            if note > 127 {
+                return Err(format!("invalid {}: '{}' is not a valid note", name, note));
+            }
+            Ok(note)
+        };
+        let first_note = note_value(lines.next(), "first note")?;
+        let last_note = note_value(lines.next(), "last note")?;
+        let middle_note = note_value(lines.next(), "middle note")?;
+        let reference_note = note_value(lines.next(), "reference note")?;
+        let reference_frequency = scala_value::<f64>(lines.next(), "reference frequency")?;
+        if reference_frequency <= 0.0 {
+            return Err("reference frequency must be > 0".to_string());
+        }
+        let octave_degree = scala_value::<usize>(lines.next(), "octave degree")?;
+        let mut map = Vec::with_capacity(size);
+        for index in 0..size {
+            // missing trailing entries are unmapped
+            let entry = lines
+                .next()
+                .and_then(|line| line.split_whitespace().next())
+                .unwrap_or("x");
+            if entry.eq_ignore_ascii_case("x") {
+                map.push(None);
+            } else {
+                let degree = entry
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid mapping entry #{}: '{}'", index + 1, entry))?;
+                map.push(Some(degree));
+            }
+        }
+        Ok(Self {
+            first_note,
+            last_note,
+            middle_note,
+            reference_note,
+            reference_frequency,
+            octave_degree,
+            map,
+        })
+    }
+
+    /// Load a keyboard mapping from a Scala keyboard mapping (.kbm) file.
+    pub fn from_kbm_file<P: AsRef<Path>>(file_path: P) -> Result<Self, String> {
+        let content = std::fs::read_to_string(file_path.as_ref())
+            .map_err(|err| format!("failed to read keyboard mapping file: {}", err))?;
+        Self::from_kbm(&content)
+    }
+
+    /// Note key which plays the scale's first degree.
+    pub fn middle_note(&self) -> Note {
+        Note::from(self.middle_note)
+    }
+
+    /// Note key which plays the reference frequency.
+    pub fn reference_note(&self) -> Note {
+        Note::from(self.reference_note)
+    }
+
+    /// Frequency of the reference note in Hz.
+    pub fn reference_frequency(&self) -> f64 {
+        self.reference_frequency
+    }
+
+    /// true when keys are mapped linearly to scale degrees.
+    pub fn is_linear(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// A microtonal tuning, as defined in Scala scale (.scl) files, with a [`KeyboardMapping`].
+///
+/// Resolves note keys to frequencies. With the default keyboard mapping, each note key plays
+/// the next scale degree of the tuning, so e.g. a 19-EDO tuning spans 19 keys per octave.
+/// See <https://www.huygens-fokker.org/scala/scl_format.html> for details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuning {
+    description: String,
+    ratios: Vec<f64>, // degree 1..=n, where the last one is the period
+    mapping: KeyboardMapping,
+}
+
+impl Default for Tuning {
+    /// 12-tone equal temperament with A = 440 Hz.
+    fn default() -> Self {
+        Self::equal_temperament(12)
+    }
+}
+
+impl Tuning {
+    /// Create an equal temperament tuning with the given divisions of the octave.
+    ///
+    /// ### Panics
+    /// Panics when divisions is 0.
+    pub fn equal_temperament(divisions: usize) -> Self {
+        assert!(divisions > 0, "Invalid divisions: divisions must be > 0");
+        let ratios = (1..=divisions)
+            .map(|degree| 2.0_f64.powf(degree as f64 / divisions as f64))
+            .collect();
+        Self {
+            description: format!("{}-EDO", divisions),
+            ratios,
+            mapping: KeyboardMapping::default(),
+        }
+    }
+
+    /// Parse a tuning from the content of a Scala scale (.scl) file.
+    pub fn from_scl(content: &str) -> Result<Self, String> {
+        let mut lines = scala_lines(content);
+        let description = lines
+            .next()
+            .ok_or_else(|| "missing description".to_string())?
+            .trim()
+            .to_string();
+        let count = scala_value::<usize>(lines.next(), "note count")?;
+        if count == 0 {
+            return Err("note count must be > 0".to_string());
+        }
+        let mut ratios = Vec::with_capacity(count);
+        for index in 0..count {
+            let line = lines
+                .next()
+                .ok_or_else(|| format!("missing pitch value #{}", index + 1))?;
+            let token = line.split_whitespace().next().unwrap_or("");
+            let invalid_pitch = || format!("invalid pitch value #{}: '{}'", index + 1, token);
+            let ratio = if token.contains('.') {
+                // cents
+                let cents = token.parse::<f64>().map_err(|_| invalid_pitch())?;
+                2.0_f64.powf(cents / 1200.0)
+            } else if let Some((numerator, denominator)) = token.split_once('/') {
+                // ratio
+                let numerator = numerator.parse::<f64>().map_err(|_| invalid_pitch())?;
+                let denominator = denominator.parse::<f64>().map_err(|_| invalid_pitch())?;
+                numerator / denominator
+            } else {
+                // integer ratio
+                token.parse::<f64>().map_err(|_| invalid_pitch())?
+            };
+            if !ratio.is_finite() || ratio <= 0.0 {
+                return Err(invalid_pitch());
+            }
+            ratios.push(ratio);
+        }
+        Ok(Self {
+            description,
+            ratios,
+            mapping: KeyboardMapping::default(),
+        })
+    }
+
+    /// Load a tuning from a Scala scale (.scl) file.
+    pub fn from_scl_file<P: AsRef<Path>>(file_path: P) -> Result<Self, String> {
+        let content = std::fs::read_to_string(file_path.as_ref())
+            .map_err(|err| format!("failed to read scale file: {}", err))?;
+        Self::from_scl(&content)
+    }
+
+    /// Return a copy of the tuning with the given keyboard mapping.
+    #[must_use]
+    pub fn with_mapping(&self, mapping: KeyboardMapping) -> Self {
+        Self {
+            mapping,
+            ..self.clone()
+        }
+    }
+
+    /// The tuning's description.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Number of scale degrees in the tuning's period.
+    pub fn size(&self) -> usize {
+        self.ratios.len()
+    }
+
+    /// Pitches of all scale degrees in the tuning's period in cents, excluding the implicit
+    /// first degree 0.0.
+    pub fn cents(&self) -> Vec<f64> {
+        self.ratios
+            .iter()
+            .map(|ratio| 1200.0 * ratio.log2())
+            .collect()
+    }
+
+    /// The tuning's keyboard mapping.
+    pub fn mapping(&self) -> &KeyboardMapping {
+        &self.mapping
+    }
+
+    /// Scale degrees for each note key in a repeating period of keys, starting from the
+    /// mapping's middle note, where None marks unmapped keys.
+    pub fn key_degrees(&self) -> Vec<Option<usize>> {
+        if self.mapping.is_linear() {
+            (0..self.size()).map(Some).collect()
+        } else {
+            self.mapping.map.clone()
+        }
+    }
+
+    /// Frequency of the given note key in Hz, or None when the key is not mapped.
+    pub fn frequency(&self, note: Note) -> Option<f64> {
+        let ratio = self.key_ratio(note as u8)?;
+        let reference_ratio = self.key_ratio(self.mapping.reference_note).unwrap_or(1.0);
+        Some(self.mapping.reference_frequency * ratio / reference_ratio)
+    }
+
+    /// Playback speed of a sample with the given note key, relative to the 12-TET frequency of
+    /// the key 60, or None when the key is not mapped.
+    pub fn playback_speed(&self, note: Note) -> Option<f64> {
+        self.frequency(note)
+            .map(|frequency| frequency / DEFAULT_BASE_FREQUENCY)
+    }
+
+    // frequency ratio of the given key, relative to the mapping's middle note
+    fn key_ratio(&self, key: u8) -> Option<f64> {
+        if key < self.mapping.first_note || key > self.mapping.last_note {
+            return None;
+        }
+        let offset = key as i64 - self.mapping.middle_note as i64;
+        let degree = if self.mapping.is_linear() {
+            offset
+        } else {
+            let map_size = self.mapping.map.len() as i64;
+            let map_octave = offset.div_euclid(map_size);
+            let map_degree = self.mapping.map[offset.rem_euclid(map_size) as usize]?;
+            map_degree as i64 + map_octave * self.mapping.octave_degree as i64
+        };
+        let size = self.size() as i64;
+        let period = self.ratios[self.ratios.len() - 1];
+        let scale_degree = degree.rem_euclid(size) as usize;
+        let ratio = if scale_degree == 0 {
+            1.0
+        } else {
+            self.ratios[scale_degree - 1]
+        };
+        Some(ratio * period.powi(degree.div_euclid(size) as i32))
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_frequency(tuning: &Tuning, note: Note, frequency: f64) {
+        let note_frequency = tuning.frequency(note).expect("expecting a mapped note");
+        assert!(
+            (note_frequency - frequency).abs() < 0.01,
+            "expected {} Hz, got {} Hz",
+            frequency,
+            note_frequency
+        );
+    }
+
+    #[test]
+    fn scala_scales() {
+        assert!(Tuning::from_scl("").is_err());
+        assert!(Tuning::from_scl("empty\n0\n").is_err());
+        assert!(Tuning::from_scl("missing\n2\n100.0\n").is_err());
+        assert!(Tuning::from_scl("invalid\n1\nabc\n").is_err());
+        assert!(Tuning::from_scl("negative\n1\n-3/2\n").is_err());
+
+        let tuning = Tuning::from_scl(
+            "! just.scl\n\
+             !\n\
+             Just major\n \
+             7\n\
+             !\n \
+             9/8\n \
+             5/4 major third\n \
+             4/3\n \
+             3/2\n \
+             5/3\n \
+             15/8\n \
+             2\n",
+        )
+        .unwrap();
+        assert_eq!(tuning.description(), "Just major");
+        assert_eq!(tuning.size(), 7);
+        assert_eq!(tuning.key_degrees().len(), 7);
+        assert!((tuning.cents()[6] - 1200.0).abs() < 1e-9);
+
+        // the default mapping tunes key 69 to 440 Hz and maps each key to the next degree
+        assert_frequency(&tuning, Note::A5, 440.0);
+        assert_frequency(&tuning, Note::C5, 176.0);
+        assert_frequency(&tuning, Note::D5, 220.0);
+        assert_frequency(&tuning, Note::G5, 352.0);
+
+        // equal temperaments match the cents notation
+        let edo_content = (1..=19)
+            .map(|step| format!("{:.5}\n", step as f64 * 1200.0 / 19.0))
+            .collect::<String>();
+        let edo = Tuning::from_scl(&format!("19-EDO\n19\n{}", edo_content)).unwrap();
+        let edo_cents = Tuning::equal_temperament(19).cents();
+        for (cents, edo_cents) in edo.cents().iter().zip(edo_cents) {
+            assert!((cents - edo_cents).abs() < 1e-3);
+        }
+
+        // 12-TET resolves to the default note frequencies and playback speeds
+        let tuning = Tuning::default();
+        assert_frequency(&tuning, Note::A5, 440.0);
+        assert_frequency(&tuning, Note::A4, 220.0);
+        assert!((tuning.playback_speed(Note::C5).unwrap() - 1.0).abs() < 1e-9);
+        assert!((tuning.playback_speed(Note::C6).unwrap() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn keyboard_mappings() {
+        assert!(KeyboardMapping::from_kbm("").is_err());
+        assert!(KeyboardMapping::from_kbm("0\n0\n127\n60\n69\n-440\n0\n").is_err());
+        assert!(KeyboardMapping::from_kbm("1\n0\n127\n60\n69\n440\n1\ny\n").is_err());
+
+        // white keys only mapping of a 7 note scale, with C5 = 264 Hz
+        let mapping = KeyboardMapping::from_kbm(
+            "! white.kbm\n\
+             12\n\
+             0\n\
+             127\n\
+             60\n\
+             60\n\
+             264.0\n\
+             7\n\
+             0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\n6\n",
+        )
+        .unwrap();
+        assert!(!mapping.is_linear());
+        assert_eq!(mapping.middle_note(), Note::C5);
+
+        let tuning = Tuning::from_scl("just\n7\n9/8\n5/4\n4/3\n3/2\n5/3\n15/8\n2/1\n")
+            .unwrap()
+            .with_mapping(mapping);
+        assert_eq!(
+            tuning.key_degrees(),
+            vec![
+                Some(0),
+                None,
+                Some(1),
+                None,
+                Some(2),
+                Some(3),
+                None,
+                Some(4),
+                None,
+                Some(5),
+                None,
+                Some(6)
+            ]
+        );
+        assert_frequency(&tuning, Note::C5, 264.0);
+        assert_frequency(&tuning, Note::E5, 330.0);
+        assert_frequency(&tuning, Note::A5, 440.0);
+        assert_frequency(&tuning, Note::C6, 528.0);
+        assert_frequency(&tuning, Note::G4, 198.0);
+        assert_eq!(tuning.frequency(Note::Cs5), None);
+    }
+}
@@ -0,0 +1,41 @@
+---@meta
+---Do not try to execute this file. It's just a type definition file.
+---
+---Part of the afseq trait: Defines LuaLS annotations for the afseq arpeggiator function.
+---
+
+----------------------------------------------------------------------------------------------------
+
+---@class Arpeggiator
+Arpeggiator = {}
+
+---Available arpeggiator modes.
+---@alias ArpeggiatorMode "up"|"down"|"updown"|"random"|"played"
+
+---Arpeggiator options.
+---@class ArpeggiatorOptions
+---Note order: lowest to highest ("up"), highest to lowest ("down"), up and back down
+---("updown"), randomly picked ("random") or in the order of the emitted notes ("played").
+---By default "up".
+---@field mode ArpeggiatorMode?
+---Number of octaves the notes are repeated in, clamped to 10. By default 1.
+---@field octaves integer?
+---Number of notes played per pulse, e.g. 2 plays two notes per pulse, 0.5 a note every
+---second pulse, clamped to 64. By default 1.
+---@field rate number?
+
+----------------------------------------------------------------------------------------------------
+
+---Create an emitter which arpeggiates the notes of the given emitter's chords.
+---
+---The given emitter runs with each pulse, and its notes are played one after another
+---instead of all at once. Note-offs stop the arpeggiator.
+---@param emit Cycle|Sequence|Note|NoteValue|(NoteValue|Note)[]|(fun(context: EmitterContext):NoteValue)|(fun(context: EmitterContext):fun(context: EmitterContext):NoteValue)
+---@param options ArpeggiatorOptions?
+---@return Arpeggiator
+---### examples:
+---```lua
+---arp(chord("c4", "minor7"), {mode = "updown", octaves = 2})
+---arp(sequence("c4'maj", "g4'maj"), {rate = 4})
+---```
+function arp(emit, options) end
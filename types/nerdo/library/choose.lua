@@ -0,0 +1,30 @@
+---@meta
+---Do not try to execute this file. It's just a type definition file.
+---
+---Part of the afseq trait: Defines LuaLS annotations for the afseq choose function.
+---
+
+----------------------------------------------------------------------------------------------------
+
+---@class WeightedChoice
+WeightedChoice = {}
+
+---A note value and its weight. The probability of a choice to get picked is its weight relative
+---to the sum of all weights.
+---@alias WeightedChoiceValue { [1]: Note|NoteValue, [2]: number }
+
+----------------------------------------------------------------------------------------------------
+
+---Create an emitter which randomly picks one of the given notes in each step, respecting the
+---notes' weights.
+---
+---Choices are made in the rhythm's emitter, without running a Lua callback in each step. Use
+---`math.randomseed` to get reproducible results.
+---@param choices WeightedChoiceValue[]
+---@return WeightedChoice
+---### examples:
+---```lua
+---choose({ {"c4", 5}, {"e4", 3}, {"g4", 2} }) -- c4 in 50%, e4 in 30%, g4 in 20% of all steps
+---choose({ {"c4'maj", 1}, {"a4'min", 1}, {"off", 0.5} })
+---```
+function choose(choices) end
@@ -0,0 +1,95 @@
+---@meta
+---Do not try to execute this file. It's just a type definition file.
+---
+---Part of the afseq trait: Defines LuaLS annotations for the afseq Cycle class.
+---
+
+----------------------------------------------------------------------------------------------------
+
+---@class Cycle
+Cycle = {}
+
+----------------------------------------------------------------------------------------------------
+
+---Create a note emitter from a Tidal Cycles mini-notation string.
+---
+---Each emitted event plays back an entire cycle: notes within the cycle are spread over the
+---duration of a single pulse. See https://tidalcycles.org/docs/reference/mini_notation/
+---for an introduction of the mini-notation syntax.
+---
+---Pitches can be played as chords by appending a chord mode, an optional inversion and
+---an optional number of notes, e.g. `"c4'maj"`, `"e4'min7'ii"` or `"g'maj'5"`.
+---
+---Target values set note properties, using the same prefixes as note strings: `#` (instrument),
+---`v` (volume), `p` (panning) or `d` (delay), e.g. `"c4:v0.5 e4:p-1 g4:#3"`.
+---@param input string
+---@return Cycle
+---### examples:
+---```lua
+---cycle("c4 [e4 g4] <a4 b4> ~")
+---cycle("c4 e4, g3 ~ g3")
+---cycle("c4'maj e4'min7'ii")
+---cycle("c4:v0.5 e4:p-1 g4:#3")
+---```
+function cycle(input) end
+
+----------------------------------------------------------------------------------------------------
+
+---Play the cycle faster by the given factor: a factor of 2 plays two cycles within one cycle.
+---@param factor number Must be > 0, gets clamped to 1024 at most
+---@return Cycle
+---### examples:
+---```lua
+---cycle("c4 e4"):fast(2)
+---```
+function Cycle:fast(factor) end
+
+---Play the cycle slower by the given factor: a factor of 2 spreads a cycle over two cycles.
+---@param factor number Must be > 0, gets clamped to 1024 at most
+---@return Cycle
+function Cycle:slow(factor) end
+
+---Reverse the events within each cycle.
+---@return Cycle
+function Cycle:rev() end
+
+---Apply the given transform function every `n`th cycle, starting with the first one.
+---@param n integer Must be > 0
+---@param transform fun(cycle: Cycle):Cycle
+---@return Cycle
+---### examples:
+---```lua
+---cycle("c4 e4 g4"):every(3, function(c) return c:rev() end)
+---```
+function Cycle:every(n, transform) end
+
+---Stack the cycle with a transformed copy of itself, shifted by the given fraction of a cycle.
+---@param time number
+---@param transform fun(cycle: Cycle):Cycle
+---@return Cycle
+---### examples:
+---```lua
+---cycle("c4 e4"):off(0.25, function(c) return c:fast(2) end)
+---```
+function Cycle:off(time, transform) end
+
+---Randomly remove events with the given probability.
+---@param amount number Probability in range [0 - 1]
+---@return Cycle
+function Cycle:degrade_by(amount) end
+
+---Play the cycle forwards, then backwards in every other cycle.
+---@return Cycle
+function Cycle:palindrome() end
+
+---Divide the cycle into `n` parts and shift the cycle's start by one part in each cycle.
+---@param n integer Must be > 0
+---@return Cycle
+function Cycle:iter(n) end
+
+---Divide the cycle into `n` parts and apply the given transform function to one part in
+---each cycle, in turn.
+---@param n integer Must be > 0
+---@param transform fun(cycle: Cycle):Cycle
+---@return Cycle
+function Cycle:chunk(n, transform) end
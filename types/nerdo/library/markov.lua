@@ -0,0 +1,32 @@
+---@meta
+---Do not try to execute this file. It's just a type definition file.
+---
+---Part of the afseq trait: Defines LuaLS annotations for the afseq markov function.
+---
+
+----------------------------------------------------------------------------------------------------
+
+---@class Markov
+Markov = {}
+
+---Markov chain options.
+---@class MarkovOptions
+---Number of preceding notes the next note depends on, > 0 and clamped to 16. By default 1.
+---@field order integer?
+
+----------------------------------------------------------------------------------------------------
+
+---Create an emitter which learns the note transitions of the given sequence and then emits
+---new, randomly chosen sequences from the learned transitions.
+---
+---The sequence is learned as if it was looped, and playback starts with the first `order`
+---notes of the sequence. Use `math.randomseed` to get reproducible results.
+---@param sequence Sequence|Note|NoteValue|(NoteValue|Note)[]
+---@param options MarkovOptions?
+---@return Markov
+---### examples:
+---```lua
+---markov(sequence("c4", "e4", "g4", "e4", "c4", "a4"), {order = 2})
+---markov({"c4", "d4", "e4", "c4", "g4"})
+---```
+function markov(sequence, options) end
@@ -12,6 +12,9 @@
 ---@field volume number? Volume in range [0.0 - 1.0]
 ---@field panning number? Panning factor in range [-1.0 - 1.0] where 0 is center
 ---@field delay number? Delay factor in range [0.0 - 1.0]
+---@field length number? Length relative to the step >= 0.0. When nil, plays until the next note.
+---@field glide number? Glide time from the previous note relative to the step >= 0.0. When nil, retriggers.
+---@field [integer] number? Custom parameter values, keyed by parameter id, e.g. `[74] = 0.5`
 NoteTable = {}
 
 ----------------------------------------------------------------------------------------------------
@@ -67,6 +70,25 @@ function Note:with_panning(panning) end
 ---@return Note
 function Note:with_delay(delay) end
 
+---Create a copy of the note or chord with new length values.
+---@param length number|number[] new length relative to the step >= 0
+---@return Note
+---### examples:
+---```lua
+---note("c'maj"):with_length(0.5)
+---```
+function Note:with_length(length) end
+
+---Create a copy of the note or chord with new glide values. Gliding notes slide the pitch of
+---the note which is already playing on the same voice, instead of retriggering it.
+---@param glide number|number[] new glide time relative to the step >= 0
+---@return Note
+---### examples:
+---```lua
+---note("c4"):with_glide(0.25)
+---```
+function Note:with_glide(glide) end
+
 ----------------------------------------------------------------------------------------------------
 
 ---@alias NoteValue NoteTable|string|number|nil
@@ -81,6 +103,8 @@ function Note:with_delay(delay) end
 --- -'v' -> volume (float in range [0-1])
 --- -'p' -> panning (float in range [-1-1])
 --- -'d' -> delay (float in range [0-1])
+--- -'l' -> length (float >= 0, relative to the step)
+--- -'g' -> glide (float >= 0, relative to the step)
 ---```
 ---@param ... NoteValue
 ---@return Note
@@ -89,7 +113,10 @@ function Note:with_delay(delay) end
 --- note(60) -- middle C
 --- note("c4") -- middle C
 --- note("c4 #2 v0.5 d0.3") -- middle C with additional properties
+--- note("c4 l0.5") -- middle C, stopped after half of the step
+--- note("c4 g0.5") -- middle C, gliding from the previous note in half of the step
 --- note({key="c4", volume=0.5}) -- middle C with volume 0.5
+--- note({key="c4", [74]=0.8}) -- middle C with custom parameter 74 set to 0.8
 --- note("c4'maj v0.7") -- C4 major chord with volume 0.7
 --- note("c4", "e4 v0.5", "off") -- custom chord with a c4, e4 and 'off' note
 --- ```
@@ -218,8 +218,14 @@ error("Do not try to execute this file. It's just a type definition file.")
 ---emit = pattern.from(tritone:chord(1, 4)):euclidean(6) +
 ---  pattern.from(tritone:chord(5, 4)):euclidean(6)
 ---
+----- a tidal mini-notation cycle, emitting an entire cycle per pulse
+---emit = cycle("c4 [e4 g4] <a4 b4> ~")
+---
+----- an arpeggiated chord
+---emit = arp(chord("c4", "minor7"), {mode = "updown", octaves = 2})
+---
 ---```
----@field emit Sequence|Note|NoteValue|(NoteValue|Note)[]|(fun(context: EmitterContext):NoteValue)|(fun(context: EmitterContext):fun(context: EmitterContext):NoteValue)
+---@field emit Arpeggiator|Markov|WeightedChoice|Cycle|Sequence|Note|NoteValue|(NoteValue|Note)[]|(fun(context: EmitterContext):NoteValue)|(fun(context: EmitterContext):fun(context: EmitterContext):NoteValue)
 
 
 ----------------------------------------------------------------------------------------------------
@@ -94,3 +94,22 @@ function scale(key, mode) end
 ---scale("c4", {0,3,5,7}).notes -> {"c4", "d#4", "f4", "g4", "a4"}
 ---```
 function scale(key, intervals) end
+
+---Create a new scale instance from the given key and a Scala scale (.scl) file.
+---The scale contains all degrees of the tuning, where each following note key plays the next
+---degree, so e.g. a 19-EDO scale spans 19 keys per octave. To hear the tuned pitches, the
+---notes must be played back with the same tuning.
+---
+---An optional Scala keyboard mapping (.kbm) file maps keys to the tuning's degrees instead.
+---The scale then only contains the mapped keys, starting from the given key, which must be
+---a mapped key.
+---@param key string|number e.g. "c4" or 48
+---@param scala_file string path to a .scl file
+---@param mapping_file string? path to a .kbm file
+---@return Scale
+---### examples:
+---```lua
+---scale("c5", "tunings/19edo.scl").notes -> {60, 61, 62, ..., 78}
+---scale("d5", "tunings/just.scl", "tunings/white_keys.kbm").notes -> {62, 64, 65, ..., 72}
+---```
+function scale(key, scala_file, mapping_file) end
@@ -56,6 +56,16 @@ function Sequence:with_panning(panning) end
 ---@return Sequence
 function Sequence:with_delay(delay) end
 
+---Create a copy of all notes in the sequence with new length values.
+---@param length number|number[] new length relative to the step >= 0
+---@return Sequence
+function Sequence:with_length(length) end
+
+---Create a copy of all notes in the sequence with new glide values.
+---@param glide number|number[] new glide time relative to the step >= 0
+---@return Sequence
+function Sequence:with_glide(glide) end
+
 ----------------------------------------------------------------------------------------------------
 
 ---Create a sequence from an array of note values or note value varargs.